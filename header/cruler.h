@@ -3,10 +3,27 @@
 #ifndef CRULER_H
 #define CRULER_H
 
+#include <stddef.h>
+
 extern "C" {
+	// headers are "Name: value" lines separated by "\r\n", the pointers have to stay valid
+	// until the callback is called again
+	struct cruler_transport_response {
+		int status;
+		const char* headers;
+		const unsigned char* body;
+		size_t body_len;
+	};
+
+	// returns 0 on success, body is null when the request has none
+	typedef int (*cruler_transport)(void* user_data, const char* method, const char* url,
+		const char* headers, const unsigned char* body, size_t body_len,
+		cruler_transport_response* response);
+
 	void cruler_extract_all_with_default_config();
 	void cruler_extract_all_from_raw(const char* ext_rule_raw, const char* config_raw);
 	void cruler_extract_all(const char* config_path);
+	void cruler_extract_all_with_transport(const char* config_path, cruler_transport transport, void* user_data);
 }
 
 #endif // !CRULER_H
\ No newline at end of file
@@ -1,4 +1,4 @@
-use configure::RuleUtils;
+use configure::{RuleUtils,ConnectorConfigure};
 
 #[test]
 fn find_labels_test() {
@@ -16,4 +16,21 @@ fn get_matched_test() {
     let matched = RuleUtils::get_matched(content, pattern, label_name);
     let first_matched = matched[0].as_str();
     assert_eq!("http://monolev.com/", first_matched);
+}
+
+#[test]
+fn connector_configure_merge_test() {
+    use super::toml;
+    use std::time::Duration;
+
+    let global: ConnectorConfigure = toml::from_str("request_delay_ms = 200").unwrap();
+    let rule: ConnectorConfigure = toml::from_str("max_requests_per_second = 2.0").unwrap();
+
+    let merged = global.merge(Some(&rule));
+    assert_eq!(Some(200), merged.get_request_delay_ms());
+    assert_eq!(Some(Duration::from_millis(500)), merged.get_request_interval());
+
+    let merged = global.merge(None);
+    assert_eq!(Some(Duration::from_millis(200)), merged.get_request_interval());
+    assert_eq!(None, ConnectorConfigure::default().get_request_interval());
 }
\ No newline at end of file
@@ -0,0 +1,177 @@
+use std::env;
+use std::rc::Rc;
+use std::fs::File;
+use std::io::Read;
+use std::cell::RefCell;
+use std::collections::{BTreeMap,HashMap};
+
+use hyper::Request;
+use hyper::header::{Authorization,Basic,Bearer};
+
+use super::super::toml;
+use configure::{ConnectorConfigure,AuthConfigure};
+
+const DEFAULT_API_KEY_HEADER: &'static str = "X-Api-Key";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Credentials {
+    Basic { username: String, password: String },
+    Bearer(String),
+    ApiKey { header: String, key: String },
+}
+
+//NOTE: credentials by host pattern, secrets are looked up once when the settings are made
+#[derive(Clone, Default)]
+pub struct AuthSettings {
+    entries: Vec<(String, Credentials)>,
+}
+
+impl AuthSettings {
+    //NOTE: a secret is read from the environment variable of its name first, then from the secrets file,
+    //      an entry with a secret found in neither is skipped, a secrets file that can not be loaded is an error
+    pub fn from_configure(configure: &ConnectorConfigure, secrets_cache: &SecretsCache) -> Result<AuthSettings, String> {
+        let auth = match configure.get_auth() {
+            Some(auth) => auth,
+            None => return Ok(AuthSettings::default()),
+        };
+        let secrets = match configure.get_secrets_file() {
+            Some(secrets_file) => secrets_cache.load(secrets_file.as_str())?,
+            None => Rc::new(BTreeMap::new()),
+        };
+
+        let mut entries = Vec::new();
+        for (host_pattern, auth_configure) in auth {
+            match AuthSettings::credentials_of(auth_configure, &secrets) {
+                Ok(credentials) => entries.push((host_pattern.trim().to_lowercase(), credentials)),
+                Err(msg) => warn!("AuthSettings::from_configure - auth of {} is skipped : {}", host_pattern, msg),
+            }
+        }
+        Ok(AuthSettings {
+            entries: entries,
+        })
+    }
+
+    fn credentials_of(configure: &AuthConfigure, secrets: &BTreeMap<String, String>) -> Result<Credentials, String> {
+        let secret = |name: Option<&String>, field: &str| {
+            match name {
+                Some(name) => AuthSettings::secret_of(name.as_str(), secrets)
+                    .ok_or(format!("secret {} of {} is not set", name, field)),
+                None => Err(format!("{} is missing", field)),
+            }
+        };
+
+        match configure.get_scheme().trim().to_lowercase().as_str() {
+            "basic" => Ok(Credentials::Basic {
+                username: secret(configure.get_username(), "username")?,
+                password: secret(configure.get_password(), "password")?,
+            }),
+            "bearer" => Ok(Credentials::Bearer(secret(configure.get_token(), "token")?)),
+            "api-key" => Ok(Credentials::ApiKey {
+                header: configure.get_header().cloned().unwrap_or(DEFAULT_API_KEY_HEADER.to_owned()),
+                key: secret(configure.get_token(), "token")?,
+            }),
+            scheme => Err(format!("{} is not an auth scheme", scheme)),
+        }
+    }
+
+    fn secret_of(name: &str, secrets: &BTreeMap<String, String>) -> Option<String> {
+        match env::var(name) {
+            Ok(value) => Option::Some(value),
+            Err(_) => secrets.get(name).cloned(),
+        }
+    }
+
+    //NOTE: the exact host wins, then the longest "*.domain" that matches, then "*"
+    pub fn credentials_for(&self, host: &str) -> Option<&Credentials> {
+        let host = host.to_lowercase();
+        let mut matched: Option<(usize, &Credentials)> = Option::None;
+        for &(ref host_pattern, ref credentials) in &self.entries {
+            let rank = if *host_pattern == host {
+                usize::max_value()
+            }
+            else if host_pattern == "*" {
+                0
+            }
+            else if host_pattern.starts_with("*.") && host.ends_with(&host_pattern[1..]) {
+                host_pattern.len()
+            }
+            else {
+                continue;
+            };
+
+            match matched {
+                Some((matched_rank, _)) if matched_rank >= rank => { },
+                _ => matched = Option::Some((rank, credentials)),
+            }
+        }
+        matched.map(|(_, credentials)| credentials)
+    }
+
+    //NOTE: an Authorization or api-key header set by the rule is kept,
+    //      it is applied per attempt so a redirect to another host does not take the credentials along
+    pub fn prepare_request(&self, request: &mut Request) {
+        let credentials = match request.uri().host() {
+            Some(host) => match self.credentials_for(host) {
+                Some(credentials) => credentials.clone(),
+                None => return,
+            },
+            None => return,
+        };
+
+        let headers = request.headers_mut();
+        match credentials {
+            Credentials::Basic { username, password } => {
+                if headers.get_raw("Authorization").is_none() {
+                    headers.set(Authorization(Basic {
+                        username: username,
+                        password: Option::Some(password),
+                    }));
+                }
+            },
+            Credentials::Bearer(token) => {
+                if headers.get_raw("Authorization").is_none() {
+                    headers.set(Authorization(Bearer {
+                        token: token,
+                    }));
+                }
+            },
+            Credentials::ApiKey { header, key } => {
+                if headers.get_raw(header.as_str()).is_none() {
+                    headers.set_raw(header, key);
+                }
+            },
+        }
+    }
+}
+
+//NOTE: secrets files by path, each is read once and shared by the connectors of a session
+pub struct SecretsCache {
+    secrets: RefCell<HashMap<String, Rc<BTreeMap<String, String>>>>,
+}
+
+impl SecretsCache {
+    pub fn new() -> SecretsCache {
+        SecretsCache {
+            secrets: RefCell::new(HashMap::new()),
+        }
+    }
+
+    //NOTE: a file that fails is not kept, so it is read again by the next connector
+    pub fn load(&self, path: &str) -> Result<Rc<BTreeMap<String, String>>, String> {
+        match self.secrets.borrow().get(path) {
+            Some(secrets) => return Ok(secrets.clone()),
+            None => { },
+        }
+
+        let mut content = String::new();
+        File::open(path).and_then(|mut file| file.read_to_string(&mut content))
+            .map_err(|err| format!("secrets file {} is not read : {}", path, err))?;
+        //NOTE: the parse error is not shown, it may quote a secret
+        let secrets: BTreeMap<String, String> = toml::from_str(content.as_str())
+            .map_err(|_| format!("secrets file {} has to be NAME = \"value\" lines", path))?;
+
+        let secrets = Rc::new(secrets);
+        self.secrets.borrow_mut().insert(path.to_owned(), secrets.clone());
+        Ok(secrets)
+    }
+}
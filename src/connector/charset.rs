@@ -0,0 +1,102 @@
+use super::encoding_rs::{Encoding,UTF_8,EUC_KR,SHIFT_JIS};
+
+//NOTE: html spec only looks for <meta charset> within the first 1024 bytes
+const META_SNIFF_SIZE: usize = 1024;
+
+pub struct Charset;
+
+impl Charset {
+    //NOTE: priority is the rule override, BOM, Content-Type charset, <meta> charset and then UTF-8
+    pub fn decode(body: &[u8], header_charset: Option<&str>, override_label: Option<&str>) -> String {
+        let encoding = Charset::detect(body, header_charset, override_label);
+        let (decoded, used_encoding, had_errors) = encoding.decode(body);
+        if had_errors {
+            warn!("Charset::decode - malformed {} bytes are replaced", used_encoding.name());
+        }
+        decoded.into_owned()
+    }
+
+    pub fn detect(body: &[u8], header_charset: Option<&str>,
+                  override_label: Option<&str>) -> &'static Encoding {
+        match override_label {
+            Some(label) => match Charset::encoding_for_label(label) {
+                Some(encoding) => return encoding,
+                None => warn!("Charset::detect - unknown encoding in rule : {}", label),
+            },
+            None => { },
+        }
+
+        match Encoding::for_bom(body) {
+            Some((encoding, _bom_length)) => return encoding,
+            None => { },
+        }
+
+        match header_charset.and_then(Charset::encoding_for_label) {
+            Some(encoding) => return encoding,
+            None => { },
+        }
+
+        match Charset::sniff_meta_charset(body) {
+            Some(encoding) => return encoding,
+            None => { },
+        }
+
+        UTF_8
+    }
+
+    //NOTE: windows code page names are not WHATWG labels but still show up on korean and japanese sites
+    pub fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+        match Encoding::for_label(label.as_bytes()) {
+            Some(encoding) => Option::Some(encoding),
+            None => match label.trim().to_lowercase().as_str() {
+                "cp949" | "ms949" | "uhc" | "x-windows-949" => Option::Some(EUC_KR),
+                "cp932" | "x-ms-cp932" => Option::Some(SHIFT_JIS),
+                _ => Option::None,
+            },
+        }
+    }
+
+    //NOTE: handles <meta charset="euc-kr"> and <meta http-equiv="Content-Type" content="text/html; charset=euc-kr">
+    fn sniff_meta_charset(body: &[u8]) -> Option<&'static Encoding> {
+        let head_size = if body.len() > META_SNIFF_SIZE { META_SNIFF_SIZE } else { body.len() };
+        let head = String::from_utf8_lossy(&body[..head_size]).to_lowercase();
+
+        let mut rest = head.as_str();
+        while let Some(meta_start) = rest.find("<meta") {
+            let tag = &rest[meta_start..];
+            let tag = match tag.find('>') {
+                Some(tag_end) => &tag[..tag_end],
+                None => tag,
+            };
+            rest = &rest[meta_start + tag.len()..];
+
+            let label = match tag.find("charset") {
+                Some(charset_start) => Charset::read_label(&tag[charset_start + "charset".len()..]),
+                None => continue,
+            };
+            match label.and_then(|label| Charset::encoding_for_label(label)) {
+                //NOTE: a <meta> tag readable as ASCII means the page is not really UTF-16
+                Some(encoding) if encoding.name().starts_with("UTF-16") => return Option::Some(UTF_8),
+                Some(encoding) => return Option::Some(encoding),
+                None => { },
+            }
+        }
+        Option::None
+    }
+
+    fn read_label(after_charset: &str) -> Option<&str> {
+        let value = after_charset.trim_start();
+        if !value.starts_with('=') {
+            return Option::None;
+        }
+        let value = value[1..].trim_start().trim_start_matches(|c| c == '"' || c == '\'');
+        let label_end = value.find(|c: char| !(c.is_ascii_alphanumeric() || "-_:.".contains(c)))
+            .unwrap_or(value.len());
+        if label_end == 0 {
+            Option::None
+        }
+        else {
+            Option::Some(&value[..label_end])
+        }
+    }
+}
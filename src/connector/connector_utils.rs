@@ -100,4 +100,55 @@ impl ConnectorUtils {
 
         built_uri.as_str().parse().unwrap()
     }
-}
\ No newline at end of file
+
+    //NOTE: resolves a link such as a Location header value against the uri it was received from
+    pub fn resolve_uri(base: &Uri, link: &str) -> Option<Uri> {
+        let link = link.trim();
+        match link.parse::<Uri>() {
+            Ok(ref link_uri) if link_uri.is_absolute() => return Option::Some(link_uri.clone()),
+            _ => { },
+        }
+
+        let scheme = match base.scheme() {
+            Some(scheme) => scheme,
+            None => return Option::None,
+        };
+        let authority = match base.authority() {
+            Some(authority) => authority,
+            None => return Option::None,
+        };
+
+        let mut resolved = String::new();
+        if link.starts_with("//") {
+            resolved.push_str(scheme);
+            resolved.push(':');
+            resolved.push_str(link);
+        }
+        else {
+            resolved.push_str(scheme);
+            resolved.push_str("://");
+            resolved.push_str(authority);
+            if link.starts_with("/") {
+                resolved.push_str(link);
+            }
+            else if link.starts_with("?") {
+                resolved.push_str(base.path());
+                resolved.push_str(link);
+            }
+            else {
+                let path = base.path();
+                let directory = match path.rfind('/') {
+                    Some(slash_index) => &path[..slash_index + 1],
+                    None => "/",
+                };
+                resolved.push_str(directory);
+                resolved.push_str(link);
+            }
+        }
+
+        match resolved.parse() {
+            Ok(uri) => Option::Some(uri),
+            Err(_) => Option::None,
+        }
+    }
+}
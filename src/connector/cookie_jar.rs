@@ -0,0 +1,266 @@
+use std::cell::RefCell;
+use std::time::{Duration,SystemTime};
+
+use hyper::{Uri,Request};
+use hyper::client::Response;
+use hyper::header::HttpDate;
+
+#[derive(Clone, Debug)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    domain: String,
+    host_only: bool,
+    path: String,
+    expires: Option<SystemTime>,
+    secure: bool,
+}
+
+impl Cookie {
+    //NOTE: parses a single Set-Cookie value received from request_uri, invalid or foreign cookies are None
+    pub fn parse(set_cookie: &str, request_uri: &Uri) -> Option<Cookie> {
+        let request_host = match request_uri.host() {
+            Some(host) => host.to_lowercase(),
+            None => return Option::None,
+        };
+
+        let mut attributes = set_cookie.split(';');
+        let name_value = attributes.next().unwrap_or("");
+        let (name, value) = match name_value.find('=') {
+            Some(index) => (name_value[..index].trim(), name_value[index + 1..].trim()),
+            None => return Option::None,
+        };
+        if name.is_empty() {
+            return Option::None;
+        }
+
+        let mut domain: Option<String> = Option::None;
+        let mut path: Option<String> = Option::None;
+        let mut expires: Option<SystemTime> = Option::None;
+        let mut max_age: Option<SystemTime> = Option::None;
+        let mut secure = false;
+        for attribute in attributes {
+            let (key, attribute_value) = match attribute.find('=') {
+                Some(index) => (attribute[..index].trim(), attribute[index + 1..].trim()),
+                None => (attribute.trim(), ""),
+            };
+
+            match key.to_lowercase().as_str() {
+                "domain" => {
+                    let attribute_value = attribute_value.trim_start_matches('.').to_lowercase();
+                    if !attribute_value.is_empty() {
+                        domain = Option::Some(attribute_value);
+                    }
+                },
+                "path" => {
+                    if attribute_value.starts_with('/') {
+                        path = Option::Some(attribute_value.to_owned());
+                    }
+                },
+                "expires" => {
+                    match attribute_value.parse::<HttpDate>() {
+                        Ok(date) => expires = Option::Some(SystemTime::from(date)),
+                        Err(_) => {
+                            debug!("Cookie::parse - invalid expires of {} : {}", name, attribute_value);
+                        },
+                    }
+                },
+                "max-age" => {
+                    //NOTE: zero or negative max-age expires the cookie right away
+                    match attribute_value.parse::<i64>() {
+                        Ok(seconds) if seconds > 0 => {
+                            max_age = Option::Some(SystemTime::now() + Duration::from_secs(seconds as u64));
+                        },
+                        Ok(_) => max_age = Option::Some(SystemTime::UNIX_EPOCH),
+                        Err(_) => { },
+                    }
+                },
+                "secure" => secure = true,
+                _ => { },
+            }
+        }
+
+        //NOTE: a Domain without a dot ("com") would reach every host under it, it is only kept as a host-only
+        //      cookie when it is the host itself ("localhost"), there is no public suffix list so a domain
+        //      like "co.kr" still goes through
+        let (domain, host_only) = match domain {
+            Some(ref domain) if !domain.contains('.') => {
+                if *domain != request_host {
+                    warn!("Cookie::parse - {} rejected, domain {} is a top-level domain", name, domain);
+                    return Option::None;
+                }
+                (request_host, true)
+            },
+            Some(domain) => {
+                if !Cookie::domain_match(request_host.as_str(), domain.as_str()) {
+                    warn!("Cookie::parse - {} rejected, domain {} doesn't match {}",
+                          name, domain, request_host);
+                    return Option::None;
+                }
+                (domain, false)
+            },
+            None => (request_host, true),
+        };
+
+        let path = match path {
+            Some(path) => path,
+            None => Cookie::default_path(request_uri.path()),
+        };
+
+        Option::Some(Cookie {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            domain: domain,
+            host_only: host_only,
+            path: path,
+            expires: max_age.or(expires),
+            secure: secure,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn value(&self) -> &str {
+        self.value.as_str()
+    }
+
+    pub fn domain(&self) -> &str {
+        self.domain.as_str()
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        match self.expires {
+            Some(expires) => expires <= now,
+            None => false,
+        }
+    }
+
+    pub fn matches(&self, uri: &Uri) -> bool {
+        let host = match uri.host() {
+            Some(host) => host.to_lowercase(),
+            None => return false,
+        };
+
+        let domain_matched = if self.host_only {
+            host == self.domain
+        }
+        else {
+            Cookie::domain_match(host.as_str(), self.domain.as_str())
+        };
+        let secure_matched = !self.secure || uri.scheme() == Some("https");
+
+        domain_matched && secure_matched && Cookie::path_match(uri.path(), self.path.as_str())
+    }
+
+    fn is_same(&self, other: &Cookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+
+    fn domain_match(host: &str, domain: &str) -> bool {
+        host == domain || host.ends_with(format!(".{}", domain).as_str())
+    }
+
+    //NOTE: "/" matches everything, "/webtoon" matches "/webtoon", "/webtoon/" and "/webtoon/list.nhn"
+    fn path_match(request_path: &str, cookie_path: &str) -> bool {
+        if request_path == cookie_path {
+            return true;
+        }
+        if !request_path.starts_with(cookie_path) {
+            return false;
+        }
+        cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')
+    }
+
+    fn default_path(request_path: &str) -> String {
+        match request_path.rfind('/') {
+            Some(index) if index > 0 => request_path[..index].to_owned(),
+            _ => "/".to_owned(),
+        }
+    }
+}
+
+//NOTE: shared through Rc by every connector of a run, so cookies set while navigating are sent while extracting
+pub struct CookieJar {
+    cookies: RefCell<Vec<Cookie>>,
+}
+
+impl CookieJar {
+    pub fn new() -> CookieJar {
+        CookieJar {
+            cookies: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.borrow().len()
+    }
+
+    pub fn insert(&self, cookie: Cookie) {
+        let mut cookies = self.cookies.borrow_mut();
+        cookies.retain(|stored| !stored.is_same(&cookie));
+        if !cookie.is_expired(SystemTime::now()) {
+            cookies.push(cookie);
+        }
+    }
+
+    pub fn store_response(&self, request_uri: &Uri, response: &Response) {
+        let set_cookies = match response.headers().get_raw("Set-Cookie") {
+            Some(set_cookies) => set_cookies,
+            None => return,
+        };
+
+        for set_cookie in set_cookies.iter() {
+            let set_cookie = String::from_utf8_lossy(set_cookie);
+            match Cookie::parse(set_cookie.as_ref(), request_uri) {
+                Some(cookie) => {
+                    debug!("CookieJar::store_response - {} from {}", cookie.name(), request_uri);
+                    self.insert(cookie);
+                },
+                None => { },
+            }
+        }
+    }
+
+    //NOTE: longer paths go first as browsers do
+    pub fn cookie_header(&self, uri: &Uri) -> Option<String> {
+        let now = SystemTime::now();
+        let mut cookies = self.cookies.borrow_mut();
+        cookies.retain(|cookie| !cookie.is_expired(now));
+
+        let mut matched: Vec<&Cookie> = cookies.iter().filter(|cookie| cookie.matches(uri)).collect();
+        if matched.is_empty() {
+            return Option::None;
+        }
+        matched.sort_by(|lhs, rhs| rhs.path.len().cmp(&lhs.path.len()));
+
+        let pairs: Vec<String> = matched.iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect();
+        Option::Some(pairs.join("; "))
+    }
+
+    //NOTE: a Cookie header already set on the request is kept and the stored cookies are appended
+    pub fn prepare_request(&self, request: &mut Request) {
+        let cookie_header = match self.cookie_header(request.uri()) {
+            Some(cookie_header) => cookie_header,
+            None => return,
+        };
+
+        let headers = request.headers_mut();
+        let cookie_header = match headers.get_raw("Cookie").and_then(|raw| raw.one()) {
+            Some(existing) => format!("{}; {}", String::from_utf8_lossy(existing), cookie_header),
+            None => cookie_header,
+        };
+        headers.set_raw("Cookie", cookie_header);
+    }
+}
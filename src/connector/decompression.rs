@@ -0,0 +1,178 @@
+use std::io;
+use std::io::Write;
+use std::mem;
+
+use hyper::{Chunk,Error};
+
+use super::futures::{Async,Poll,Stream};
+use super::flate2::write::{GzDecoder,ZlibDecoder,DeflateDecoder};
+use super::brotli_decompressor::DecompressorWriter;
+
+pub const ACCEPT_ENCODING: &'static str = "gzip, deflate, br";
+
+const BROTLI_BUFFER_SIZE: usize = 4096;
+
+//NOTE: every decoder writes into a Vec that is drained after each chunk
+pub enum ContentDecoder {
+    Gzip(GzDecoder<Vec<u8>>),
+    Zlib(ZlibDecoder<Vec<u8>>),
+    Deflate(DeflateDecoder<Vec<u8>>),
+    Brotli(DecompressorWriter<Vec<u8>>),
+    //NOTE: "deflate" is zlib wrapped by the spec but some servers send raw deflate, the first bytes tell
+    UndecidedDeflate(Vec<u8>),
+}
+
+impl ContentDecoder {
+    //NOTE: None for identity and for encodings that are not supported, the body is passed through then
+    pub fn from_content_encoding(content_encoding: &str) -> Option<ContentDecoder> {
+        match content_encoding.trim().to_lowercase().as_str() {
+            "gzip" | "x-gzip" => Option::Some(ContentDecoder::Gzip(GzDecoder::new(Vec::new()))),
+            "deflate" => Option::Some(ContentDecoder::UndecidedDeflate(Vec::new())),
+            "br" => Option::Some(ContentDecoder::Brotli(DecompressorWriter::new(Vec::new(),
+                                                                                BROTLI_BUFFER_SIZE))),
+            _ => Option::None,
+        }
+    }
+
+    pub fn decode(&mut self, input: &[u8]) -> io::Result<Vec<u8>> {
+        let decided = match *self {
+            ContentDecoder::UndecidedDeflate(ref mut head) => {
+                head.extend_from_slice(input);
+                if head.len() < 2 {
+                    return Ok(Vec::new());
+                }
+                Option::Some(ContentDecoder::decide_deflate(head))
+            },
+            _ => Option::None,
+        };
+
+        match decided {
+            Some((decoder, head)) => {
+                *self = decoder;
+                self.write(head.as_slice())
+            },
+            None => self.write(input),
+        }
+    }
+
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            ContentDecoder::Gzip(decoder) => decoder.finish(),
+            ContentDecoder::Zlib(decoder) => decoder.finish(),
+            ContentDecoder::Deflate(decoder) => decoder.finish(),
+            ContentDecoder::Brotli(mut decoder) => {
+                decoder.close()?;
+                match decoder.into_inner() {
+                    Ok(output) => Ok(output),
+                    Err(_) => Err(io::Error::new(io::ErrorKind::InvalidData, "brotli stream is incomplete")),
+                }
+            },
+            ContentDecoder::UndecidedDeflate(head) => {
+                if head.is_empty() {
+                    return Ok(Vec::new());
+                }
+                let (mut decoder, head) = ContentDecoder::decide_deflate(&head);
+                let mut output = decoder.write(head.as_slice())?;
+                output.extend(decoder.finish()?);
+                Ok(output)
+            },
+        }
+    }
+
+    fn write(&mut self, input: &[u8]) -> io::Result<Vec<u8>> {
+        let output = match *self {
+            ContentDecoder::Gzip(ref mut decoder) => {
+                decoder.write_all(input)?;
+                decoder.get_mut()
+            },
+            ContentDecoder::Zlib(ref mut decoder) => {
+                decoder.write_all(input)?;
+                decoder.get_mut()
+            },
+            ContentDecoder::Deflate(ref mut decoder) => {
+                decoder.write_all(input)?;
+                decoder.get_mut()
+            },
+            ContentDecoder::Brotli(ref mut decoder) => {
+                decoder.write_all(input)?;
+                decoder.get_mut()
+            },
+            ContentDecoder::UndecidedDeflate(_) => return Ok(Vec::new()),
+        };
+        Ok(mem::replace(output, Vec::new()))
+    }
+
+    //NOTE: a zlib header has 8 in the low bits of the first byte and is a multiple of 31 as a big endian u16
+    fn decide_deflate(head: &Vec<u8>) -> (ContentDecoder, Vec<u8>) {
+        let is_zlib = head.len() >= 2 && head[0] & 0x0f == 8 &&
+            ((head[0] as u16) << 8 | head[1] as u16) % 31 == 0;
+        let decoder = if is_zlib {
+            ContentDecoder::Zlib(ZlibDecoder::new(Vec::new()))
+        }
+        else {
+            ContentDecoder::Deflate(DeflateDecoder::new(Vec::new()))
+        };
+        (decoder, head.clone())
+    }
+}
+
+//NOTE: decodes the chunks of a body as they arrive instead of buffering the whole body
+pub struct DecodingBody<S> {
+    inner: S,
+    decoder: Option<ContentDecoder>,
+    received: bool,
+}
+
+impl<S> DecodingBody<S> {
+    pub fn new(inner: S, decoder: ContentDecoder) -> DecodingBody<S> {
+        DecodingBody {
+            inner: inner,
+            decoder: Option::Some(decoder),
+            received: false,
+        }
+    }
+
+    fn decode_error(err: io::Error) -> Error {
+        warn!("DecodingBody - failed to decode the body : {}", err);
+        Error::Io(err)
+    }
+}
+
+impl<S> Stream for DecodingBody<S>
+    where S: Stream<Item=Chunk, Error=Error> {
+    type Item = Chunk;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Chunk>, Error> {
+        loop {
+            if self.decoder.is_none() {
+                return Ok(Async::Ready(Option::None));
+            }
+
+            match self.inner.poll()? {
+                Async::Ready(Some(chunk)) => {
+                    self.received = true;
+                    let decoded = match self.decoder {
+                        Some(ref mut decoder) => decoder.decode(&chunk).map_err(DecodingBody::<S>::decode_error)?,
+                        None => Vec::new(),
+                    };
+                    if !decoded.is_empty() {
+                        return Ok(Async::Ready(Option::Some(Chunk::from(decoded))));
+                    }
+                },
+                Async::Ready(None) => {
+                    //NOTE: HEAD, 204 and 304 responses keep Content-Encoding without any body
+                    let decoder = self.decoder.take().unwrap();
+                    if !self.received {
+                        return Ok(Async::Ready(Option::None));
+                    }
+                    let decoded = decoder.finish().map_err(DecodingBody::<S>::decode_error)?;
+                    if !decoded.is_empty() {
+                        return Ok(Async::Ready(Option::Some(Chunk::from(decoded))));
+                    }
+                },
+                Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+    }
+}
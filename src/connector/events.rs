@@ -0,0 +1,103 @@
+use std::thread;
+use std::panic::{self,AssertUnwindSafe};
+use std::collections::HashMap;
+
+use connector::futures::{Stream,Poll};
+use connector::futures::sync::mpsc::{self,UnboundedSender,UnboundedReceiver};
+use connector::extractor::asset_writer::StoredAsset;
+use result::ResultHandler;
+
+//NOTE: what Navigator and Extractor report while they run, rule is the name of the rule it happened in
+#[derive(Clone, Debug)]
+pub enum CrawlEvent {
+    PageFetched { rule: String, url: String },
+    ItemExtracted { rule: String, source_url: String, labels: HashMap<String, Vec<String>> },
+    AssetStored { rule: String, asset: StoredAsset },
+    //NOTE: url is none when the rule failed before any request, e.g. at login
+    Error { rule: String, url: Option<String>, message: String },
+}
+
+impl CrawlEvent {
+    pub fn item_of(rule: &str, source_url: &str, result_handler: &ResultHandler) -> CrawlEvent {
+        let mut labels = HashMap::new();
+        for label_name in result_handler.get_label_names() {
+            let values = result_handler.get_result(label_name.as_str()).cloned().unwrap_or(Vec::new());
+            labels.insert(label_name, values);
+        }
+
+        CrawlEvent::ItemExtracted {
+            rule: rule.to_owned(),
+            source_url: source_url.to_owned(),
+            labels: labels,
+        }
+    }
+}
+
+//NOTE: the sending half handed to Navigator and Extractor, the default one sends nowhere
+#[derive(Clone, Default)]
+pub struct EventSink {
+    sender: Option<UnboundedSender<CrawlEvent>>,
+}
+
+impl EventSink {
+    pub fn channel() -> (EventSink, EventStream) {
+        let (sender, receiver) = mpsc::unbounded();
+        let sink = EventSink {
+            sender: Option::Some(sender),
+        };
+        let stream = EventStream {
+            receiver: receiver,
+        };
+        (sink, stream)
+    }
+
+    //NOTE: a dropped stream only means nobody listens anymore, the run goes on
+    pub fn send(&self, event: CrawlEvent) {
+        match self.sender {
+            Some(ref sender) => {
+                let _ = sender.unbounded_send(event);
+            },
+            None => { },
+        }
+    }
+}
+
+//NOTE: ends when the run it listens to is over, it can be polled from any executor
+pub struct EventStream {
+    receiver: UnboundedReceiver<CrawlEvent>,
+}
+
+impl EventStream {
+    //NOTE: runs on a thread of its own, a panic of the run is reported as the last event of the stream
+    pub fn spawn<F>(run: F) -> EventStream
+        where F: FnOnce(EventSink) + Send + 'static {
+        let (events, stream) = EventSink::channel();
+        thread::spawn(move || {
+            let sink = events.clone();
+            match panic::catch_unwind(AssertUnwindSafe(move || run(sink))) {
+                Ok(_) => { },
+                Err(payload) => {
+                    let reason = match payload.downcast_ref::<&str>() {
+                        Some(reason) => reason.to_string(),
+                        None => payload.downcast_ref::<String>().cloned().unwrap_or("unknown".to_owned()),
+                    };
+                    events.send(CrawlEvent::Error {
+                        rule: String::new(),
+                        url: Option::None,
+                        message: format!("the run panicked : {}", reason),
+                    });
+                },
+            }
+        });
+        stream
+    }
+}
+
+impl Stream for EventStream {
+    type Item = CrawlEvent;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<CrawlEvent>, ()> {
+        self.receiver.poll()
+    }
+}
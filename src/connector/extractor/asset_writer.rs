@@ -0,0 +1,390 @@
+use std::fs;
+use std::io::{Read,Write};
+use std::path::PathBuf;
+
+use hyper;
+use hyper::{Body,Request,StatusCode};
+use hyper::client::Response;
+
+use connector::futures::{Future,Stream};
+use connector::futures::future::{self,Loop};
+use connector::sniff::{SniffedType,SNIFF_SIZE};
+
+pub enum StoreOutcome {
+    Stored(StoredAsset),
+    Skipped(String),
+}
+
+//NOTE: what is known about a stored file, content_type is the declared one and sniffed_type comes from the bytes
+#[derive(Clone, Debug)]
+pub struct StoredAsset {
+    url: String,
+    path: String,
+    content_type: Option<String>,
+    sniffed_type: Option<SniffedType>,
+}
+
+impl StoredAsset {
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    pub fn content_type(&self) -> Option<&String> {
+        self.content_type.as_ref()
+    }
+
+    pub fn sniffed_type(&self) -> Option<SniffedType> {
+        self.sniffed_type
+    }
+}
+
+//NOTE: a download in progress lives in <path_stem>.part, <path_stem>.part.meta holds its url, validator
+//      and the media type sniffed when it started
+pub struct PartialDownload {
+    part_path: String,
+    meta_path: String,
+    size: u64,
+    validator: String,
+    sniffed_type: Option<SniffedType>,
+}
+
+impl PartialDownload {
+    //NOTE: a partial without a validator, of another url or too short to sniff is not resumed
+    pub fn load(path_stem: &str, url: &str) -> Option<PartialDownload> {
+        let part_path = PartialDownload::part_path_of(path_stem);
+        let meta_path = PartialDownload::meta_path_of(path_stem);
+
+        let mut meta = String::new();
+        match fs::File::open(meta_path.as_str()).and_then(|mut file| file.read_to_string(&mut meta)) {
+            Ok(_) => { },
+            Err(_) => return Option::None,
+        }
+        let mut lines = meta.lines();
+        match lines.next() {
+            Some(part_url) if part_url == url => { },
+            _ => return Option::None,
+        }
+        let validator = match lines.next() {
+            Some(validator) if !validator.is_empty() => validator.to_owned(),
+            _ => return Option::None,
+        };
+        //NOTE: a meta written without the media type leaves it to be sniffed from the head on disk
+        let sniffed_type = lines.next().and_then(|media_type| media_type.parse::<SniffedType>().ok());
+        let size = match fs::metadata(part_path.as_str()) {
+            Ok(metadata) if metadata.len() >= SNIFF_SIZE as u64 => metadata.len(),
+            _ => return Option::None,
+        };
+
+        Option::Some(PartialDownload {
+            part_path: part_path,
+            meta_path: meta_path,
+            size: size,
+            validator: validator,
+            sniffed_type: sniffed_type,
+        })
+    }
+
+    pub fn part_path_of(path_stem: &str) -> String {
+        format!("{}.part", path_stem)
+    }
+
+    pub fn meta_path_of(path_stem: &str) -> String {
+        format!("{}.part.meta", path_stem)
+    }
+
+    //NOTE: If-Range needs a strong validator, so a weak ETag falls back to Last-Modified
+    pub fn validator_of(response: &Response) -> Option<String> {
+        let header_value = |name: &str| {
+            response.headers().get_raw(name)
+                .and_then(|raw| raw.one())
+                .map(|value| String::from_utf8_lossy(value).trim().to_owned())
+                .and_then(|value| if value.is_empty() { Option::None } else { Option::Some(value) })
+        };
+        match header_value("ETag") {
+            Some(ref etag) if !etag.starts_with("W/") => Option::Some(etag.clone()),
+            _ => header_value("Last-Modified"),
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn validator(&self) -> &str {
+        self.validator.as_str()
+    }
+
+    pub fn sniffed_type(&self) -> Option<SniffedType> {
+        self.sniffed_type
+    }
+
+    //NOTE: the partial holds decoded bytes, so the rest is asked for without any content coding
+    pub fn prepare_request(&self, request: &mut Request) {
+        let headers = request.headers_mut();
+        headers.set_raw("Accept-Encoding", "identity");
+        headers.set_raw("Range", format!("bytes={}-", self.size));
+        headers.set_raw("If-Range", self.validator.clone());
+    }
+
+    //NOTE: a 206 continues the partial only when it starts where the partial ends under the same validator
+    pub fn accepts(&self, response: &Response) -> bool {
+        if response.status() != StatusCode::PartialContent {
+            return false;
+        }
+        match PartialDownload::validator_of(response) {
+            Some(ref validator) if validator.as_str() != self.validator.as_str() => return false,
+            _ => { },
+        }
+        PartialDownload::range_start_of(response) == Option::Some(self.size)
+    }
+
+    //NOTE: Content-Range is "bytes <first>-<last>/<complete length>"
+    pub fn range_start_of(response: &Response) -> Option<u64> {
+        let content_range = match response.headers().get_raw("Content-Range").and_then(|raw| raw.one()) {
+            Some(content_range) => String::from_utf8_lossy(content_range).into_owned(),
+            None => return Option::None,
+        };
+        content_range.trim().splitn(2, ' ').nth(1)
+            .and_then(|range| range.split('-').next())
+            .and_then(|first| first.trim().parse::<u64>().ok())
+    }
+
+    pub fn remove(&self) {
+        let _ = fs::remove_file(self.part_path.as_str());
+        let _ = fs::remove_file(self.meta_path.as_str());
+    }
+}
+
+//NOTE: holds back the first bytes until the type is sniffed, then writes <path_stem>.part
+//      and renames it after the sniffed type once the body is complete
+pub struct AssetWriter {
+    url: String,
+    path_stem: String,
+    content_type: Option<String>,
+    declared_extension: String,
+    expected_types: Option<Vec<SniffedType>>,
+    max_bytes: Option<u64>,
+    validator: Option<String>,
+    resume: Option<PartialDownload>,
+    written: u64,
+    head: Vec<u8>,
+    file: Option<(fs::File, String, Option<SniffedType>)>,
+}
+
+impl AssetWriter {
+    pub fn new(url: String, path_stem: String,
+               content_type: Option<String>, declared_extension: String,
+               expected_types: Option<Vec<SniffedType>>) -> AssetWriter {
+        AssetWriter {
+            url: url,
+            path_stem: path_stem,
+            content_type: content_type,
+            declared_extension: declared_extension,
+            expected_types: expected_types,
+            max_bytes: Option::None,
+            validator: Option::None,
+            resume: Option::None,
+            written: 0,
+            head: Vec::new(),
+            file: Option::None,
+        }
+    }
+
+    //NOTE: a body growing past max_bytes is aborted and its partial file removed
+    pub fn set_max_bytes(&mut self, max_bytes: Option<u64>) {
+        self.max_bytes = max_bytes;
+    }
+
+    //NOTE: an interrupted download is kept for resuming only when its response had a validator
+    pub fn set_validator(&mut self, validator: Option<String>) {
+        self.validator = validator;
+    }
+
+    //NOTE: the body is appended to the partial, the response has to be accepted by PartialDownload::accepts
+    pub fn resume_from(&mut self, partial: PartialDownload) {
+        self.validator = Option::Some(partial.validator().to_owned());
+        self.resume = Option::Some(partial);
+    }
+
+    //NOTE: a skipped download stops reading the body, which drops the connection
+    pub fn store(mut self, body: Body) -> Box<Future<Item=StoreOutcome, Error=hyper::Error>> {
+        if self.resume.is_some() {
+            match self.open_resumed() {
+                Ok(Some(reason)) => return Box::new(future::ok(StoreOutcome::Skipped(reason))),
+                Ok(None) => { },
+                Err(err) => return Box::new(future::err(err)),
+            }
+        }
+
+        let work = future::loop_fn((body, self), |(body, mut writer)| {
+            body.into_future().map_err(|(err, _body)| err).and_then(move |(chunk, body)| {
+                match chunk {
+                    Some(chunk) => match writer.write_chunk(&chunk)? {
+                        Some(reason) => Ok(Loop::Break(StoreOutcome::Skipped(reason))),
+                        None => Ok(Loop::Continue((body, writer))),
+                    },
+                    None => writer.finish().map(Loop::Break),
+                }
+            })
+        });
+        Box::new(work)
+    }
+
+    fn write_chunk(&mut self, chunk: &[u8]) -> Result<Option<String>, hyper::Error> {
+        self.written += chunk.len() as u64;
+        match self.max_bytes {
+            Some(max_bytes) if self.written > max_bytes => {
+                self.discard();
+                return Ok(Option::Some(format!("{} is larger than {} bytes, aborted", self.url, max_bytes)));
+            },
+            _ => { },
+        }
+
+        match self.file {
+            Some((ref mut file, _, _)) => {
+                file.write_all(chunk)?;
+                return Ok(Option::None);
+            },
+            None => { },
+        }
+
+        self.head.extend_from_slice(chunk);
+        if self.head.len() < SNIFF_SIZE {
+            return Ok(Option::None);
+        }
+        self.open_file()
+    }
+
+    fn finish(mut self) -> Result<StoreOutcome, hyper::Error> {
+        if self.file.is_none() {
+            match self.open_file()? {
+                Some(reason) => return Ok(StoreOutcome::Skipped(reason)),
+                None => { },
+            }
+        }
+
+        let (file, path, sniffed_type) = self.file.take().unwrap();
+        drop(file);
+        fs::rename(PartialDownload::part_path_of(self.path_stem.as_str()), path.as_str())?;
+        let _ = fs::remove_file(PartialDownload::meta_path_of(self.path_stem.as_str()));
+
+        Ok(StoreOutcome::Stored(StoredAsset {
+            url: self.url.clone(),
+            path: path,
+            content_type: self.content_type.clone(),
+            sniffed_type: sniffed_type,
+        }))
+    }
+
+    fn discard(&mut self) {
+        match self.file.take() {
+            Some((file, _path, _sniffed_type)) => drop(file),
+            None => return,
+        }
+        let part_path = PartialDownload::part_path_of(self.path_stem.as_str());
+        match fs::remove_file(part_path.as_str()) {
+            Err(err) => warn!("AssetWriter::discard - {} is not removed : {}", part_path, err),
+            Ok(_) => {},
+        }
+        let _ = fs::remove_file(PartialDownload::meta_path_of(self.path_stem.as_str()));
+    }
+
+    fn check_expected(&self, sniffed_type: Option<SniffedType>) -> Option<String> {
+        match self.expected_types {
+            Some(ref expected_types) if !sniffed_type.map_or(false, |sniffed| expected_types.contains(&sniffed)) => {
+                let sniffed_name = sniffed_type.map_or("unknown", |sniffed| sniffed.name());
+                Option::Some(format!("{} is {}, not one of the expected types", self.url, sniffed_name))
+            },
+            _ => Option::None,
+        }
+    }
+
+    //NOTE: the bytes win over a missing or wrong Content-Type
+    fn file_name_of(&self, sniffed_type: Option<SniffedType>) -> String {
+        let extension = match sniffed_type {
+            Some(sniffed) => sniffed.extension(),
+            None => self.declared_extension.as_str(),
+        };
+        format!("{}.{}", self.path_stem, extension)
+    }
+
+    fn open_file(&mut self) -> Result<Option<String>, hyper::Error> {
+        let sniffed_type = SniffedType::sniff(&self.head);
+        match self.check_expected(sniffed_type) {
+            Some(reason) => return Ok(Option::Some(reason)),
+            None => { },
+        }
+
+        let file_name = self.file_name_of(sniffed_type);
+        debug!("AssetWriter::open_file - {} ({:?}, declared {:?})",
+               file_name, sniffed_type, self.content_type);
+
+        let path = PathBuf::from(file_name.clone());
+        let parent_path = path.parent().unwrap();
+        if !parent_path.exists() {
+            match fs::create_dir_all(parent_path) {
+                Err(err) => {
+                    debug!("AssetWriter::open_file - error occurred : {}", err);
+                },
+                Ok(_) => {},
+            }
+        }
+
+        //NOTE: a full body replaces whatever partial was left before
+        let meta_path = PartialDownload::meta_path_of(self.path_stem.as_str());
+        match self.validator {
+            Some(ref validator) => {
+                let mut meta = fs::File::create(meta_path.as_str())?;
+                let media_type = sniffed_type.map_or("", |sniffed| sniffed.media_type());
+                meta.write_all(format!("{}\n{}\n{}\n", self.url, validator, media_type).as_bytes())?;
+            },
+            None => {
+                let _ = fs::remove_file(meta_path.as_str());
+            },
+        }
+        let mut file = fs::File::create(PartialDownload::part_path_of(self.path_stem.as_str()))?;
+        file.write_all(&self.head)?;
+        self.head.clear();
+        self.file = Option::Some((file, file_name, sniffed_type));
+        Ok(Option::None)
+    }
+
+    //NOTE: the type sniffed when the download started is kept, the body is appended from there on
+    fn open_resumed(&mut self) -> Result<Option<String>, hyper::Error> {
+        let partial = self.resume.take().unwrap();
+        let sniffed_type = match partial.sniffed_type() {
+            Some(sniffed_type) => Option::Some(sniffed_type),
+            None => {
+                let mut head = vec![0u8; SNIFF_SIZE];
+                fs::File::open(partial.part_path.as_str()).and_then(|mut file| file.read_exact(&mut head))?;
+                SniffedType::sniff(&head)
+            },
+        };
+        match self.check_expected(sniffed_type) {
+            Some(reason) => {
+                partial.remove();
+                return Ok(Option::Some(reason));
+            },
+            None => { },
+        }
+
+        info!("AssetWriter::open_resumed - {} resumes from {} bytes", self.url, partial.size());
+        let file = fs::OpenOptions::new().append(true).open(partial.part_path.as_str())?;
+        self.written = partial.size();
+        self.file = Option::Some((file, self.file_name_of(sniffed_type), sniffed_type));
+        Ok(Option::None)
+    }
+}
+
+//NOTE: an interrupted download without a validator can never be resumed, so its partial goes away
+impl Drop for AssetWriter {
+    fn drop(&mut self) {
+        if self.validator.is_none() {
+            self.discard();
+        }
+    }
+}
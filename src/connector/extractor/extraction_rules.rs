@@ -1,5 +1,6 @@
 use std::vec::Vec;
 use std::str::FromStr;
+use std::collections::BTreeMap;
 
 use super::super::super::toml;
 use super::super::super::configure::*;
@@ -48,6 +49,14 @@ pub struct UnitExtractionRule {
     parts: Option<Vec<String>>,
     extract: Option<String>,
     procedure: Option<ProcedureRule>,
+    connector: Option<ConnectorConfigure>,
+    encoding: Option<String>,
+    headers: Option<BTreeMap<String, String>>,
+    request: Option<RequestConfigure>,
+    login: Option<LoginConfigure>,
+    expected_types: Option<Vec<String>>,
+    max_bytes: Option<u64>,
+    preflight: Option<bool>,
 }
 
 impl Clone for UnitExtractionRule {
@@ -88,6 +97,50 @@ impl Clone for UnitExtractionRule {
             },
             None => None,
         };
+        let connector = match self.connector() {
+            Some(ref_connector) => {
+                let connector = ref_connector.clone();
+                Some(connector)
+            },
+            None => None,
+        };
+        let encoding = match self.encoding() {
+            Some(ref_encoding) => {
+                let encoding = ref_encoding.clone();
+                Some(encoding)
+            },
+            None => None,
+        };
+        let headers = match self.headers() {
+            Some(ref_headers) => {
+                let headers = ref_headers.clone();
+                Some(headers)
+            },
+            None => None,
+        };
+        let request = match self.request() {
+            Some(ref_request) => {
+                let request = ref_request.clone();
+                Some(request)
+            },
+            None => None,
+        };
+        let login = match self.login() {
+            Some(ref_login) => {
+                let login = ref_login.clone();
+                Some(login)
+            },
+            None => None,
+        };
+        let expected_types = match self.expected_types() {
+            Some(ref_expected_types) => {
+                let expected_types = ref_expected_types.clone();
+                Some(expected_types)
+            },
+            None => None,
+        };
+        let max_bytes = self.max_bytes();
+        let preflight = self.preflight();
 
         UnitExtractionRule {
             name: name,
@@ -96,6 +149,14 @@ impl Clone for UnitExtractionRule {
             parts: parts,
             extract: extract,
             procedure: procedure,
+            connector: connector,
+            encoding: encoding,
+            headers: headers,
+            request: request,
+            login: login,
+            expected_types: expected_types,
+            max_bytes: max_bytes,
+            preflight: preflight,
         }
     }
 }
@@ -126,6 +187,45 @@ impl UnitExtractionRule {
     pub fn procedure(&self) -> Option<&ProcedureRule> {
         self.procedure.as_ref()
     }
+
+    pub fn connector(&self) -> Option<&ConnectorConfigure> {
+        self.connector.as_ref()
+    }
+
+    //NOTE: overrides the charset detected from the response, e.g. "euc-kr" or "shift_jis"
+    pub fn encoding(&self) -> Option<&String> {
+        self.encoding.as_ref()
+    }
+
+    //NOTE: added to [connector.headers], e.g. Referer = "{source_url}"
+    pub fn headers(&self) -> Option<&BTreeMap<String, String>> {
+        self.headers.as_ref()
+    }
+
+    //NOTE: how the requests of links are sent, e.g. a search form that only answers POST
+    pub fn request(&self) -> Option<&RequestConfigure> {
+        self.request.as_ref()
+    }
+
+    //NOTE: used instead of the global [login]
+    pub fn login(&self) -> Option<&LoginConfigure> {
+        self.login.as_ref()
+    }
+
+    //NOTE: store downloads whose sniffed type is not listed, e.g. ["jpeg", "png", "webp"], are skipped
+    pub fn expected_types(&self) -> Option<&Vec<String>> {
+        self.expected_types.as_ref()
+    }
+
+    //NOTE: store downloads larger than this are skipped, by Content-Length or once the streamed bytes pass it
+    pub fn max_bytes(&self) -> Option<u64> {
+        self.max_bytes
+    }
+
+    //NOTE: sends HEAD before each store download to check Content-Length and Content-Type first
+    pub fn preflight(&self) -> Option<bool> {
+        self.preflight
+    }
 }
 
 pub enum ProcedureName {
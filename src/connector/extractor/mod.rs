@@ -1,56 +1,145 @@
+use std::str::FromStr;
 use std::collections::{BTreeMap,VecDeque};
 
 use super::futures::*;
 use super::futures::stream::Stream;
 
 use super::hyper;
-use hyper::header::{Raw};
-use hyper::{Request,Method};
+use hyper::{Request,Method,StatusCode};
 
 use connector::navigator::navigation_rules::NavigationRules;
 use connector::navigator::Navigator;
 use connector::{Connector,HeaderContentType};
+use connector::session::Session;
+use connector::request_headers::RequestHeaders;
+use connector::request_template::RequestTemplate;
+use connector::login::Login;
+use connector::events::{CrawlEvent,EventSink,EventStream};
+use connector::charset::Charset;
+use connector::media_type::{MimeExtensions,UNKNOWN_EXTENSION};
+use connector::sniff::SniffedType;
 use configure::*;
 use result::*;
 
 pub mod extraction_rules;
 pub mod specific_procedure;
+pub mod asset_writer;
 use self::extraction_rules::{ExtractionRules,UnitExtractionRule,ProcedureRule,ProcedureName};
 use self::specific_procedure::{SpecificProcedure,DefaultProcedure,NaverWebtoonProcedure};
+use self::asset_writer::{AssetWriter,PartialDownload,StoreOutcome,StoredAsset};
 
 pub struct Extractor<'a, 'b> {
     rules: &'a ExtractionRules,
     config: &'b ExtractorConfigure,
+    connector_config: Option<&'b ConnectorConfigure>,
+    login: Option<&'b LoginConfigure>,
+    session: Session,
+    events: EventSink,
 }
 
 impl<'a, 'b> Extractor<'a, 'b> {
     pub fn new(rule_config: &'a ExtractionRules,
-               system_config: &'b ExtractorConfigure) -> Extractor<'a, 'b> {
+               system_config: &'b ExtractorConfigure,
+               connector_config: Option<&'b ConnectorConfigure>) -> Extractor<'a, 'b> {
+        Extractor::with_session(rule_config, system_config, connector_config, Session::new())
+    }
+
+    pub fn with_session(rule_config: &'a ExtractionRules,
+                        system_config: &'b ExtractorConfigure,
+                        connector_config: Option<&'b ConnectorConfigure>,
+                        session: Session) -> Extractor<'a, 'b> {
         Extractor {
             rules: rule_config,
             config: system_config,
+            connector_config: connector_config,
+            login: Option::None,
+            session: session,
+            events: EventSink::default(),
         }
     }
 
-    pub fn extract_all(&self) {
+    //NOTE: extracts every rule on a thread of its own and hands back what happens as it happens,
+    //      the caller polls the stream from its own executor and the run ends with the stream
+    pub fn stream_all(rules: ExtractionRules, configure: Configure) -> EventStream {
+        EventStream::spawn(move |events| {
+            let ext_configure = match configure.get_extractor_configure() {
+                Some(ext_configure) => ext_configure,
+                None => {
+                    events.send(CrawlEvent::Error {
+                        rule: String::new(),
+                        url: Option::None,
+                        message: "[extractor] is missing in the configure".to_owned(),
+                    });
+                    return;
+                },
+            };
+
+            let mut extractor = Extractor::new(&rules, ext_configure, configure.get_connector_configure());
+            extractor.set_login(configure.get_login_configure());
+            extractor.set_events(events);
+            extractor.extract_all();
+        })
+    }
+
+    //NOTE: the navigation done for rules without links reports to the same sink
+    pub fn set_events(&mut self, events: EventSink) {
+        self.events = events;
+    }
+
+    //NOTE: the global [login], done once per session before the first rule that is not logged in by its own
+    pub fn set_login(&mut self, login: Option<&'b LoginConfigure>) {
+        self.login = login;
+    }
+
+    //NOTE: the navigation done for rules without links shares this session as well
+    pub fn get_session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn extract_all(&self) -> Vec<ExtractionReport> {
+        let mut reports = Vec::new();
         let extraction_rules = self.rules.extraction();
         for rule in extraction_rules {
-            self.extract(rule);
+            let report = self.extract(rule);
+            info!("Extractor::extract_all - {} : {} pages fetched, {} pages skipped, {} pages failed, {} stored, {} store skipped, {} store failed",
+                  report.name(), report.fetched_pages(), report.skipped_pages().len(),
+                  report.failed_pages().len(), report.stored(), report.skipped_stores().len(), report.failed_stores().len());
+            reports.push(report);
         }
+        reports
     }
 
-    fn extract(&self, rule: &UnitExtractionRule) {
-        let mut conn = Connector::new();
-        Extractor::set_entry_links(&mut conn, rule);
+    fn extract(&self, rule: &UnitExtractionRule) -> ExtractionReport {
+        let mut report = ExtractionReport::new(rule.name().as_str());
+        match self.log_in(rule) {
+            Err(err) => {
+                error!("Extractor::extract - {} is not extracted : {}", rule.name(), err);
+                self.events.send(CrawlEvent::Error {
+                    rule: rule.name().clone(),
+                    url: Option::None,
+                    message: err.to_string(),
+                });
+                return report;
+            },
+            Ok(_) => { },
+        }
+        let mut conn = self.make_connector(rule);
+        self.set_entry_links(&mut conn, rule);
 
         let request_urls = conn.request_urls();
         let request_urls = &request_urls;
-        let extract_contents_result =
-            conn.run_request_all(|index, response| {
-                let source_url = &request_urls[index];
+        let events = &self.events;
+        let extract_contents_results =
+            conn.run_request_each(|index, response| {
+                //NOTE: relative links resolve against the url after redirects
+                let source_url = match Connector::get_response_url(&response) {
+                    Some(response_url) => response_url,
+                    None => request_urls[index].clone(),
+                };
                 let unit_response_handler =
-                    UnitExtractionRuleResponseHandler::new(index, source_url.clone(), rule);
+                    UnitExtractionRuleResponseHandler::new(index, source_url, rule);
                 let header_type = Connector::get_content_type(&response);
+                let header_charset = header_type.charset();
                 let will_be_okay = match header_type {
                     HeaderContentType::Text(_header) => {
                         true
@@ -60,8 +149,6 @@ impl<'a, 'b> Extractor<'a, 'b> {
                     },
                 };
 
-                let raw_cookies: Option<Raw> = Connector::get_raw_cookies(&response);
-
                 response.body().fold(Vec::new(), move |mut v, chunk| {
                     if !will_be_okay {
                         return future::failed(hyper::Error::Header);
@@ -69,79 +156,295 @@ impl<'a, 'b> Extractor<'a, 'b> {
                     v.extend(&chunk[..]);
                     future::ok::<_, hyper::Error>(v)
                 }).and_then(move |chunks| {
-                    let body_content = match String::from_utf8(chunks) {
-                        Ok(body) => body,
-                        Err(err) => {
-                            return future::failed(hyper::Error::Utf8(err.utf8_error()));
-                        }
-                    };
+                    let body_content =
+                        Charset::decode(&chunks, header_charset.as_ref().map(String::as_str),
+                                        rule.encoding().map(String::as_str));
 
                     debug!("Extractor::extract - body_content : {}", (&body_content).as_str());
 
                     let part_contents = unit_response_handler.part_from_content(body_content);
                     let extract_contents: Vec<ResultHandler> =
-                        unit_response_handler.extract_from_parts(part_contents, raw_cookies);
-
-                    future::ok(extract_contents)
+                        unit_response_handler.extract_from_parts(part_contents);
+
+                    let source_url = unit_response_handler.source_url.as_str();
+                    events.send(CrawlEvent::PageFetched {
+                        rule: rule.name().clone(),
+                        url: source_url.to_owned(),
+                    });
+                    for extract_content in &extract_contents {
+                        events.send(CrawlEvent::item_of(rule.name().as_str(), source_url, extract_content));
+                    }
+                    future::ok::<_, hyper::Error>(extract_contents)
                 })
             });
 
-        match extract_contents_result {
-            Ok(extract_contents) => {
-                Extractor::handle_results(extract_contents, rule);
-            },
-            Err(err) => {
-                debug!("Extractor::extract - error occurred : {}", err);
-            },
+        let mut extract_contents = Vec::new();
+        for (index, extract_contents_result) in extract_contents_results.into_iter().enumerate() {
+            match extract_contents_result {
+                Ok(contents) => {
+                    report.fetched_pages += 1;
+                    extract_contents.push(contents);
+                },
+                Err(ref err) if Connector::is_robots_disallowed(err) => {
+                    report.skipped_pages.push(request_urls[index].clone());
+                },
+                Err(err) => {
+                    let source_url = &request_urls[index];
+                    warn!("Extractor::extract - {} failed : {}", source_url, err);
+                    self.events.send(CrawlEvent::Error {
+                        rule: rule.name().clone(),
+                        url: Option::Some(source_url.clone()),
+                        message: err.to_string(),
+                    });
+                    report.failed_pages.push(source_url.clone());
+                },
+            }
         }
-    }
 
-    fn handle_results(results: Vec<Vec<ResultHandler>>, rule: &UnitExtractionRule) {
-        use std::fs;
-        use std::io::Write;
-        use std::path::{PathBuf};
+        self.handle_results(extract_contents, rule, &mut report);
+        report
+    }
 
-        let mut conn = Connector::new();
+    fn handle_results(&self, results: Vec<Vec<ResultHandler>>, rule: &UnitExtractionRule,
+                      report: &mut ExtractionReport) {
+        let mut conn = self.make_connector(rule);
 
+        let request_headers = self.make_request_headers(rule);
         let index_path_map =
-            Extractor::ready_for_request(&mut conn, &results, rule);
+            Extractor::ready_for_request(&mut conn, &results, rule, &request_headers);
+        let mime_extensions = MimeExtensions::new(self.config.get_mime_extensions());
+        let expected_types = Extractor::get_expected_types(rule);
+        let max_bytes = rule.max_bytes();
+        let index_path_map = if rule.preflight().unwrap_or(false) {
+            self.preflight(&mut conn, index_path_map, rule, expected_types.as_ref(), report)
+        }
+        else {
+            index_path_map
+        };
+        let store_urls = conn.request_urls();
+        let store_urls = &store_urls;
         let extraction_results =
-            conn.run_request_all(|index, response| {
+            conn.run_request_each(|index, response| {
             let path = index_path_map.get(&index).unwrap();
+            let partial = PartialDownload::load(path.as_str(), store_urls[index].as_str());
+            let resumed = match Extractor::check_resumed(&response, partial.as_ref()) {
+                Ok(resumed) => resumed,
+                Err(err) => {
+                    let failed: Box<Future<Item=StoreOutcome, Error=hyper::Error>> = Box::new(future::err(err));
+                    return failed;
+                },
+            };
 
-            let extension = match Connector::get_content_type(&response) {
-                HeaderContentType::Image(ref ext) => ext.clone(),
-                HeaderContentType::Text(ref ext) => ext.clone(),
-                HeaderContentType::Others(ref _ext) => "unknown".to_owned(),
+            //NOTE: a resumed body only holds the rest, so the limit is checked against where it starts as well
+            let range_start = if resumed { PartialDownload::range_start_of(&response).unwrap_or(0) } else { 0 };
+            match (max_bytes, Connector::get_content_length(&response)) {
+                (Some(max_bytes), Some(content_length)) if range_start + content_length > max_bytes => {
+                    match partial {
+                        Some(ref partial) => partial.remove(),
+                        None => { },
+                    }
+                    let reason = format!("Content-Length {} is larger than {} bytes", range_start + content_length, max_bytes);
+                    let skipped: Box<Future<Item=StoreOutcome, Error=hyper::Error>> =
+                        Box::new(future::ok(StoreOutcome::Skipped(reason)));
+                    return skipped;
+                },
+                _ => { },
+            }
+
+            let (content_type, extension) = match Connector::get_content_type(&response) {
+                HeaderContentType::Image(ref media_type) |
+                HeaderContentType::Text(ref media_type) |
+                HeaderContentType::Others(ref media_type) => {
+                    (Option::Some(media_type.essence()), mime_extensions.extension_of(media_type))
+                },
+                HeaderContentType::Unknown(ref reason) => {
+                    debug!("Extractor::handle_results - {} : {}", path, reason);
+                    (Option::None, UNKNOWN_EXTENSION.to_owned())
+                },
             };
 
-            let mut file_name = String::new();
-            file_name.push_str(path.as_str());
-            file_name.push_str(".");
-            file_name.push_str(extension.as_str());
-
-            //FIXME: save file
-            let path = PathBuf::from(file_name.clone());
-            let parent_path = path.parent().unwrap();
-            if !parent_path.exists() {
-                match fs::create_dir_all(parent_path) {
-                    Err(err) => {
-                        debug!("save_file - error occurred : {}", err);
-                    },
-                    Ok(_) => {},
-                }
+            let mut writer = AssetWriter::new(store_urls[index].clone(), path.clone(),
+                                              content_type, extension, expected_types.clone());
+            writer.set_max_bytes(max_bytes);
+            match partial {
+                Some(partial) if resumed => writer.resume_from(partial),
+                _ => writer.set_validator(PartialDownload::validator_of(&response)),
             }
+            let events = self.events.clone();
+            let rule_name = rule.name().clone();
+            let stored: Box<Future<Item=StoreOutcome, Error=hyper::Error>> =
+                Box::new(writer.store(response.body()).map(move |outcome| {
+                    match outcome {
+                        StoreOutcome::Stored(ref asset) => events.send(CrawlEvent::AssetStored {
+                            rule: rule_name,
+                            asset: asset.clone(),
+                        }),
+                        StoreOutcome::Skipped(_) => { },
+                    }
+                    outcome
+                }));
+            stored
+        });
+
+        for (index, extraction_result) in extraction_results.into_iter().enumerate() {
+            let store_url = &store_urls[index];
+            match extraction_result {
+                Ok(StoreOutcome::Stored(asset)) => report.stored_assets.push(asset),
+                Ok(StoreOutcome::Skipped(reason)) => {
+                    info!("Extractor::handle_results - {} skipped : {}", store_url, reason);
+                    report.skipped_stores.push(store_url.clone());
+                },
+                Err(ref err) if Connector::is_robots_disallowed(err) => {
+                    report.skipped_stores.push(store_url.clone());
+                },
+                Err(err) => {
+                    warn!("Extractor::handle_results - {} failed : {}", store_url, err);
+                    self.events.send(CrawlEvent::Error {
+                        rule: rule.name().clone(),
+                        url: Option::Some(store_url.clone()),
+                        message: err.to_string(),
+                    });
+                    report.failed_stores.push(store_url.clone());
+                },
+            }
+        }
+    }
 
-            let mut file = fs::File::create(file_name).expect("file error");
-            response.body().for_each(move |chunk| {
-                file.write_all(&chunk).map_err(From::from)
-            })
+    //NOTE: true when the response continues the partial download, a 200 starts over and anything
+    //      that can not be appended drops the partial so that the next run starts over cleanly
+    fn check_resumed(response: &hyper::client::Response,
+                     partial: Option<&PartialDownload>) -> Result<bool, hyper::Error> {
+        use std::io;
+
+        let msg = match (response.status(), partial) {
+            (StatusCode::PartialContent, Some(partial)) if partial.accepts(response) => return Ok(true),
+            (StatusCode::PartialContent, _) => "range response does not continue the partial download",
+            (StatusCode::RangeNotSatisfiable, _) => "range of the partial download is not satisfiable",
+            _ => return Ok(false),
+        };
+        match partial {
+            Some(partial) => partial.remove(),
+            None => { },
+        }
+        Err(hyper::Error::Io(io::Error::new(io::ErrorKind::InvalidData, msg)))
+    }
+
+    //NOTE: sends HEAD for every queued store and keeps only the ones worth downloading,
+    //      a failed HEAD keeps the store since many servers do not answer it properly
+    fn preflight(&self, conn: &mut Connector, index_path_map: BTreeMap<usize, String>,
+                 rule: &UnitExtractionRule, expected_types: Option<&Vec<SniffedType>>,
+                 report: &mut ExtractionReport) -> BTreeMap<usize, String> {
+        let requests = conn.take_requests();
+        let mut preflight_conn = self.make_connector(rule);
+        for request in requests.iter() {
+            let mut head_request = Request::new(Method::Head, request.uri().clone());
+            head_request.headers_mut().extend(request.headers().iter());
+            head_request.headers_mut().remove_raw("Range");
+            head_request.headers_mut().remove_raw("If-Range");
+            preflight_conn.add_request(head_request);
+        }
+
+        let max_bytes = rule.max_bytes();
+        let checks = preflight_conn.run_request_each(|_index, response| {
+            future::ok::<_, hyper::Error>(Extractor::check_preflight(&response, max_bytes, expected_types))
         });
+
+        let mut kept_path_map = BTreeMap::new();
+        for (index, (request, check)) in requests.into_iter().zip(checks.into_iter()).enumerate() {
+            let store_url = request.uri().as_ref().to_owned();
+            match check {
+                Ok(Some(reason)) => {
+                    info!("Extractor::preflight - {} skipped : {}", store_url, reason);
+                    report.skipped_stores.push(store_url);
+                    continue;
+                },
+                Err(ref err) if Connector::is_robots_disallowed(err) => {
+                    report.skipped_stores.push(store_url);
+                    continue;
+                },
+                Err(err) => {
+                    debug!("Extractor::preflight - {} failed, downloading anyway : {}", store_url, err);
+                },
+                Ok(None) => { },
+            }
+            kept_path_map.insert(conn.get_requests_count(), index_path_map.get(&index).unwrap().clone());
+            conn.add_request(request);
+        }
+        kept_path_map
+    }
+
+    fn check_preflight(response: &hyper::client::Response, max_bytes: Option<u64>,
+                       expected_types: Option<&Vec<SniffedType>>) -> Option<String> {
+        if !response.status().is_success() {
+            return Option::None;
+        }
+
+        match (max_bytes, Connector::get_content_length(response)) {
+            (Some(max_bytes), Some(content_length)) if content_length > max_bytes => {
+                return Option::Some(format!("Content-Length {} is larger than {} bytes", content_length, max_bytes));
+            },
+            _ => { },
+        }
+
+        //NOTE: only a declared type that is known and not expected is refused, the rest is sniffed while downloading
+        let declared_type = Connector::get_content_type(response).media_type()
+            .and_then(|media_type| SniffedType::from_str(media_type.essence().as_str()).ok());
+        match (expected_types, declared_type) {
+            (Some(expected_types), Some(declared_type)) if !expected_types.contains(&declared_type) => {
+                Option::Some(format!("Content-Type {} is not one of the expected types", declared_type.media_type()))
+            },
+            _ => Option::None,
+        }
+    }
+
+    fn get_expected_types(rule: &UnitExtractionRule) -> Option<Vec<SniffedType>> {
+        let names = match rule.expected_types() {
+            Some(names) => names,
+            None => return Option::None,
+        };
+
+        let mut expected_types = Vec::new();
+        for name in names {
+            match SniffedType::from_str(name.as_str()) {
+                Ok(sniffed_type) => expected_types.push(sniffed_type),
+                Err(err) => warn!("Extractor::get_expected_types - {} : {}", rule.name(), err),
+            }
+        }
+        Option::Some(expected_types)
+    }
+
+    //NOTE: the login of the rule wins over the global one
+    fn log_in(&self, rule: &UnitExtractionRule) -> Result<(), hyper::Error> {
+        match rule.login().or(self.login) {
+            Some(login) => Login::new(login).run(&self.session, self.merge_connector_configure(rule)),
+            None => Ok(()),
+        }
+    }
+
+    //NOTE: connection_pool_size of [connector] or the rule wins over the one of [extractor]
+    fn make_connector(&self, rule: &UnitExtractionRule) -> Connector {
+        let connector_config = self.merge_connector_configure(rule);
+        let pool_size = Connector::pool_size_of(&connector_config, self.config.get_connection_pool_size());
+
+        Connector::with_session(pool_size, connector_config, &self.session)
+    }
+
+    fn merge_connector_configure(&self, rule: &UnitExtractionRule) -> ConnectorConfigure {
+        match self.connector_config {
+            Some(connector_config) => connector_config.merge(rule.connector()),
+            None => ConnectorConfigure::default().merge(rule.connector()),
+        }
+    }
+
+    fn make_request_headers(&self, rule: &UnitExtractionRule) -> RequestHeaders {
+        let connector_config = self.merge_connector_configure(rule);
+        RequestHeaders::new(connector_config.get_headers(), rule.headers())
     }
 
     fn ready_for_request(conn: &mut Connector,
                          extract_targets: &Vec<Vec<ResultHandler>>,
-                         rule: &UnitExtractionRule) -> BTreeMap<usize, String> {
+                         rule: &UnitExtractionRule,
+                         request_headers: &RequestHeaders) -> BTreeMap<usize, String> {
         let mut index_path_map = BTreeMap::<usize, String>::new();
 
         let post_procedure_name = match rule.procedure() {
@@ -184,7 +487,14 @@ impl<'a, 'b> Extractor<'a, 'b> {
                             order_index += 1;
                             key_index += 1;
 
-                            let request = procedure.get_request(link);
+                            let mut request = procedure.get_request(link);
+                            let source_url = result_handler.get_extra_inform(ExtraInformKey::SourceUrl);
+                            request_headers.apply(&mut request, source_url.map(String::as_str));
+                            let partial = PartialDownload::load(path.as_str(), request.uri().as_ref());
+                            match partial {
+                                Some(partial) => partial.prepare_request(&mut request),
+                                None => { },
+                            }
                             conn.add_request(request);
                         }
                     },
@@ -203,9 +513,12 @@ impl<'a, 'b> Extractor<'a, 'b> {
         }
     }
 
-    fn get_req_links(name: &String) -> VecDeque<String> {
+    fn get_req_links(&self, name: &String) -> VecDeque<String> {
         let nav_rules = NavigationRules::default();
-        let navigator = Navigator::new(&nav_rules);
+        let mut navigator = Navigator::with_session(&nav_rules, self.connector_config,
+                                                    self.session.clone());
+        navigator.set_login(self.login);
+        navigator.set_events(self.events.clone());
         let nav_name_index_map = navigator.name_index_map();
 
         let index = match nav_name_index_map.get(name) {
@@ -224,20 +537,75 @@ impl<'a, 'b> Extractor<'a, 'b> {
         navigator.navigate(target_navigation)
     }
 
-    fn set_entry_links<'c, 'r>(conn: &'c mut Connector, rule: &'r UnitExtractionRule) {
-        match rule.links() {
-            Option::Some(links) => {
-                for link in links {
-                    conn.add_request(Request::new(Method::Get, link.parse().unwrap()));
-                }
-            },
-            Option::None => {
-                let links: VecDeque<String> = Extractor::get_req_links(rule.name());
-                for link in links {
-                    conn.add_request(Request::new(Method::Get, link.parse().unwrap()));
-                }
-            }
+    fn set_entry_links<'c, 'r>(&self, conn: &'c mut Connector, rule: &'r UnitExtractionRule) {
+        let request_headers = self.make_request_headers(rule);
+        let request_template = RequestTemplate::new(rule.request());
+        let links: Vec<String> = match rule.links() {
+            Option::Some(links) => links.clone(),
+            Option::None => self.get_req_links(rule.name()).into_iter().collect(),
         };
+
+        for link in links {
+            let mut request = request_template.make_request(link.parse().unwrap(), Option::None);
+            request_headers.apply(&mut request, Option::None);
+            conn.add_request(request);
+        }
+    }
+}
+
+pub struct ExtractionReport {
+    name: String,
+    fetched_pages: usize,
+    failed_pages: Vec<String>,
+    skipped_pages: Vec<String>,
+    stored_assets: Vec<StoredAsset>,
+    skipped_stores: Vec<String>,
+    failed_stores: Vec<String>,
+}
+
+impl ExtractionReport {
+    pub fn new(name: &str) -> ExtractionReport {
+        ExtractionReport {
+            name: name.to_owned(),
+            fetched_pages: 0,
+            failed_pages: Vec::new(),
+            skipped_pages: Vec::new(),
+            stored_assets: Vec::new(),
+            skipped_stores: Vec::new(),
+            failed_stores: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn fetched_pages(&self) -> usize {
+        self.fetched_pages
+    }
+
+    pub fn failed_pages(&self) -> &Vec<String> {
+        &self.failed_pages
+    }
+
+    pub fn skipped_pages(&self) -> &Vec<String> {
+        &self.skipped_pages
+    }
+
+    pub fn stored(&self) -> usize {
+        self.stored_assets.len()
+    }
+
+    pub fn stored_assets(&self) -> &Vec<StoredAsset> {
+        &self.stored_assets
+    }
+
+    pub fn skipped_stores(&self) -> &Vec<String> {
+        &self.skipped_stores
+    }
+
+    pub fn failed_stores(&self) -> &Vec<String> {
+        &self.failed_stores
     }
 }
 
@@ -273,9 +641,7 @@ impl <'a> UnitExtractionRuleResponseHandler<'a> {
         part_contents
     }
 
-    fn extract_from_parts(&self,
-                          part_contents: Vec<String>,
-                          raw_cookies: Option<Raw>) -> Vec<ResultHandler> {
+    fn extract_from_parts(&self, part_contents: Vec<String>) -> Vec<ResultHandler> {
         let rule = self.rule;
         let index = self.queue_index;
 
@@ -288,7 +654,7 @@ impl <'a> UnitExtractionRuleResponseHandler<'a> {
                                                      index);
                 RuleUtils::make_result_handlers(part_contents, extract_exp,
                                                 Option::Some(root_path),
-                                                self.source_url.clone(), raw_cookies)
+                                                self.source_url.clone())
             },
             Option::None => {
                 let empty_vec: Vec<ResultHandler> = Vec::new();
@@ -56,15 +56,8 @@ impl<'a> SpecificProcedure for NaverWebtoonProcedure<'a> {
     fn get_request(&self, link: &str) -> Request {
         let link = self.handler.make_requestable_uri(link);
         let mut request = Request::new(Method::Get, link);
-        match self.handler.get_raw_cookies() {
-            Some(cookie) => {
-                let mut req_headers = request.headers_mut();
-                req_headers.set_raw("Set-Cookie", cookie);
-                NaverWebtoonProcedure::add_domain_specific_headers(self.handler,
-                                                                   &mut req_headers);
-            },
-            None => { },
-        }
+        NaverWebtoonProcedure::add_domain_specific_headers(self.handler,
+                                                           request.headers_mut());
         request
     }
 }
@@ -84,14 +77,6 @@ impl<'a> DefaultProcedure<'a> {
 impl<'a> SpecificProcedure for DefaultProcedure<'a> {
     fn get_request(&self, link: &str) -> Request {
         let link = self.handler.make_requestable_uri(link);
-        let mut request = Request::new(Method::Get, link);
-        match self.handler.get_raw_cookies() {
-            Some(cookie) => {
-                let mut req_headers = request.headers_mut();
-                req_headers.set_raw("Set-Cookie", cookie);
-            },
-            None => { },
-        }
-        request
+        Request::new(Method::Get, link)
     }
 }
\ No newline at end of file
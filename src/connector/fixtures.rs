@@ -0,0 +1,96 @@
+use std::io;
+use std::str::FromStr;
+
+use hyper::{self,Request,StatusCode};
+use hyper::client::Response;
+use hyper::header::Headers;
+
+use configure::ConnectorConfigure;
+use connector::http_cache::{HttpCache,CacheWriter};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FixturesMode {
+    Record,
+    Replay,
+}
+
+impl FromStr for FixturesMode {
+    type Err = String;
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode.trim().to_lowercase().as_str() {
+            "record" => Result::Ok(FixturesMode::Record),
+            "replay" => Result::Ok(FixturesMode::Replay),
+            _ => Result::Err(format!("unknown fixtures mode : {}", mode)),
+        }
+    }
+}
+
+//NOTE: request/response pairs stored like the http cache, keyed by method and url
+pub struct Fixtures {
+    mode: FixturesMode,
+    store: HttpCache,
+}
+
+impl Fixtures {
+    pub fn new(mode: FixturesMode, dir: &str) -> Fixtures {
+        Fixtures {
+            mode: mode,
+            store: HttpCache::new(dir),
+        }
+    }
+
+    //NOTE: fixtures are on when both fixtures and fixtures_dir are set
+    pub fn from_configure(configure: &ConnectorConfigure) -> Option<Fixtures> {
+        let mode = match configure.get_fixtures() {
+            Some(mode) => match FixturesMode::from_str(mode.as_str()) {
+                Ok(mode) => mode,
+                Err(err) => {
+                    warn!("Fixtures::from_configure - {}", err);
+                    return Option::None;
+                },
+            },
+            None => return Option::None,
+        };
+
+        match configure.get_fixtures_dir() {
+            Some(dir) => Option::Some(Fixtures::new(mode, dir.as_str())),
+            None => {
+                warn!("Fixtures::from_configure - fixtures_dir is not set, fixtures are off");
+                Option::None
+            },
+        }
+    }
+
+    pub fn mode(&self) -> FixturesMode {
+        self.mode
+    }
+
+    //NOTE: requests with a body are told apart by a hash of it
+    pub fn key_of(request: &Request, body: Option<&[u8]>) -> String {
+        match body {
+            Some(body) if !body.is_empty() => {
+                format!("{} {} {:016x}", request.method(), request.uri(), HttpCache::hash_of_bytes(body))
+            },
+            _ => format!("{} {}", request.method(), request.uri()),
+        }
+    }
+
+    //NOTE: a request without a fixture fails instead of going to the network
+    pub fn replay(&self, request: &Request, body: Option<&[u8]>) -> Result<Response, hyper::Error> {
+        let key = Fixtures::key_of(request, body);
+        match self.store.lookup(key.as_str()) {
+            Some(entry) => {
+                debug!("Fixtures::replay - {}", key);
+                entry.into_replayed_response()
+            },
+            None => {
+                let msg = format!("no fixture for {}", key);
+                Err(hyper::Error::Io(io::Error::new(io::ErrorKind::NotFound, msg)))
+            },
+        }
+    }
+
+    pub fn recorder_for(&self, key: &str, status: StatusCode, headers: &Headers) -> Option<CacheWriter> {
+        self.store.writer(key, status, headers)
+    }
+}
@@ -0,0 +1,318 @@
+use std::fs;
+use std::io::{Read,Write};
+use std::path::PathBuf;
+
+use hyper::{self,Body,Chunk,Request,StatusCode};
+use hyper::client::Response;
+use hyper::header::Headers;
+
+use connector::futures::{Async,Poll,Stream};
+use configure::ConnectorConfigure;
+
+//NOTE: not replayed from the cache, cookies are only taken from real responses
+const SKIPPED_HEADERS: [&'static str; 5] =
+    ["set-cookie", "content-length", "transfer-encoding", "connection", "keep-alive"];
+
+//NOTE: besides text/*, the media types a navigation or extraction page comes in
+const PAGE_MEDIA_TYPES: [&'static str; 3] = ["application/json", "application/xml", "application/xhtml+xml"];
+
+//NOTE: bodies are stored decoded next to a meta file holding the url, the status and the headers
+pub struct HttpCache {
+    dir: PathBuf,
+}
+
+impl HttpCache {
+    pub fn new(dir: &str) -> HttpCache {
+        HttpCache {
+            dir: PathBuf::from(dir),
+        }
+    }
+
+    //NOTE: the cache is on when cache_dir is set and use_cache is not turned off
+    pub fn from_configure(configure: &ConnectorConfigure) -> Option<HttpCache> {
+        match configure.get_cache_dir() {
+            Some(cache_dir) if configure.get_use_cache().unwrap_or(true) => {
+                Option::Some(HttpCache::new(cache_dir.as_str()))
+            },
+            _ => Option::None,
+        }
+    }
+
+    //NOTE: key is the url for the cache, fixtures use the method and the url
+    pub fn lookup(&self, key: &str) -> Option<CacheEntry> {
+        let (meta_path, body_path) = self.paths_of(key);
+        let mut meta = String::new();
+        match fs::File::open(&meta_path).and_then(|mut file| file.read_to_string(&mut meta)) {
+            Ok(_) => { },
+            Err(_) => return Option::None,
+        }
+
+        let mut lines = meta.lines();
+        //NOTE: another key with the same hash is a miss
+        match lines.next() {
+            Some(cached_key) if cached_key == key => { },
+            _ => return Option::None,
+        }
+        let status = lines.next()
+            .and_then(|status| status.parse::<u16>().ok())
+            .and_then(|status| StatusCode::try_from(status).ok());
+        let status = match status {
+            Some(status) => status,
+            None => return Option::None,
+        };
+        let mut headers = Vec::new();
+        for line in lines {
+            match line.find(": ") {
+                Some(index) => headers.push((line[..index].to_owned(), line[index + 2..].to_owned())),
+                None => { },
+            }
+        }
+
+        Option::Some(CacheEntry {
+            status: status,
+            headers: headers,
+            body_path: body_path,
+        })
+    }
+
+    //NOTE: only a complete 200 page with a validator is stored, anything else would never be revalidated,
+    //      stored assets are large and already kept by AssetWriter, so they are left out
+    pub fn writer_for(&self, url: &str, status: StatusCode, headers: &Headers) -> Option<CacheWriter> {
+        if status != StatusCode::Ok || !HttpCache::is_page(headers) {
+            return Option::None;
+        }
+        if headers.get_raw("ETag").is_none() && headers.get_raw("Last-Modified").is_none() {
+            return Option::None;
+        }
+        match headers.get_raw("Cache-Control").and_then(|raw| raw.one()) {
+            Some(cache_control) if String::from_utf8_lossy(cache_control).contains("no-store") => {
+                return Option::None;
+            },
+            _ => { },
+        }
+        self.writer(url, status, headers)
+    }
+
+    //NOTE: stores any response, fixtures are recorded this way
+    pub fn writer(&self, key: &str, status: StatusCode, headers: &Headers) -> Option<CacheWriter> {
+        match fs::create_dir_all(&self.dir) {
+            Err(err) => {
+                warn!("HttpCache::writer - {} can not be created : {}", self.dir.display(), err);
+                return Option::None;
+            },
+            Ok(_) => { },
+        }
+
+        let (meta_path, body_path) = self.paths_of(key);
+        let temp_path = body_path.with_extension("tmp");
+        let file = match fs::File::create(&temp_path) {
+            Ok(file) => file,
+            Err(err) => {
+                warn!("HttpCache::writer - {} can not be created : {}", temp_path.display(), err);
+                return Option::None;
+            },
+        };
+
+        let mut meta = format!("{}\n{}\n", key, status.as_u16());
+        for header in headers.iter() {
+            if SKIPPED_HEADERS.contains(&header.name().to_lowercase().as_str()) {
+                continue;
+            }
+            meta.push_str(format!("{}: {}\n", header.name(), header.value_string()).as_str());
+        }
+
+        Option::Some(CacheWriter {
+            meta: meta,
+            meta_path: meta_path,
+            body_path: body_path,
+            temp_path: temp_path,
+            file: Option::Some(file),
+        })
+    }
+
+    fn is_page(headers: &Headers) -> bool {
+        let content_type = match headers.get_raw("Content-Type").and_then(|raw| raw.one()) {
+            Some(content_type) => String::from_utf8_lossy(content_type).into_owned(),
+            None => return false,
+        };
+        let media_type = content_type.split(';').next().unwrap_or("").trim().to_lowercase();
+        media_type.starts_with("text/") || media_type.ends_with("+json") || media_type.ends_with("+xml") ||
+            PAGE_MEDIA_TYPES.contains(&media_type.as_str())
+    }
+
+    fn paths_of(&self, key: &str) -> (PathBuf, PathBuf) {
+        let hash = format!("{:016x}", HttpCache::hash_of(key));
+        (self.dir.join(format!("{}.meta", hash)), self.dir.join(format!("{}.body", hash)))
+    }
+
+    fn hash_of(key: &str) -> u64 {
+        HttpCache::hash_of_bytes(key.as_bytes())
+    }
+
+    //NOTE: FNV-1a, stable across builds unlike the std hasher
+    pub fn hash_of_bytes(bytes: &[u8]) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+}
+
+pub struct CacheEntry {
+    status: StatusCode,
+    headers: Vec<(String, String)>,
+    body_path: PathBuf,
+}
+
+impl CacheEntry {
+    pub fn etag(&self) -> Option<&str> {
+        self.header("etag")
+    }
+
+    pub fn last_modified(&self) -> Option<&str> {
+        self.header("last-modified")
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|&&(ref header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|&(_, ref value)| value.as_str())
+    }
+
+    //NOTE: conditional headers already set by a rule are kept
+    pub fn prepare_request(&self, request: &mut Request) {
+        let headers = request.headers_mut();
+        match self.etag() {
+            Some(etag) if headers.get_raw("If-None-Match").is_none() => {
+                headers.set_raw("If-None-Match", etag.to_owned());
+            },
+            _ => { },
+        }
+        match self.last_modified() {
+            Some(last_modified) if headers.get_raw("If-Modified-Since").is_none() => {
+                headers.set_raw("If-Modified-Since", last_modified.to_owned());
+            },
+            _ => { },
+        }
+    }
+
+    //NOTE: answers a 304 with the stored response, headers of the 304 such as the response url win
+    pub fn into_response(self, not_modified: Response) -> Result<Response, hyper::Error> {
+        self.build_response(Option::Some(not_modified.headers()))
+    }
+
+    pub fn into_replayed_response(self) -> Result<Response, hyper::Error> {
+        self.build_response(Option::None)
+    }
+
+    fn build_response(self, overrides: Option<&Headers>) -> Result<Response, hyper::Error> {
+        let mut body = Vec::new();
+        fs::File::open(&self.body_path).and_then(|mut file| file.read_to_end(&mut body))?;
+
+        let mut headers = Headers::new();
+        for (name, value) in self.headers {
+            headers.set_raw(name, value);
+        }
+        for header in overrides.iter().flat_map(|overrides| overrides.iter()) {
+            if SKIPPED_HEADERS.contains(&header.name().to_lowercase().as_str()) {
+                continue;
+            }
+            headers.set_raw(header.name().to_owned(), header.value_string());
+        }
+
+        Ok(Response::new().with_status(self.status).with_headers(headers).with_body(Body::from(body)))
+    }
+}
+
+//NOTE: the body goes to a temporary file that replaces the cached one only once the body is complete
+pub struct CacheWriter {
+    meta: String,
+    meta_path: PathBuf,
+    body_path: PathBuf,
+    temp_path: PathBuf,
+    file: Option<fs::File>,
+}
+
+impl CacheWriter {
+    fn write(&mut self, chunk: &[u8]) {
+        let failed = match self.file {
+            Some(ref mut file) => file.write_all(chunk).is_err(),
+            None => false,
+        };
+        if failed {
+            warn!("CacheWriter::write - {} failed, not cached", self.temp_path.display());
+            self.abandon();
+        }
+    }
+
+    fn commit(mut self) {
+        if self.file.take().is_none() {
+            return;
+        }
+        //NOTE: the old meta goes first so that a failure never pairs it with the new body
+        let _ = fs::remove_file(&self.meta_path);
+        let committed = fs::rename(&self.temp_path, &self.body_path)
+            .and_then(|_| fs::File::create(&self.meta_path))
+            .and_then(|mut file| file.write_all(self.meta.as_bytes()));
+        match committed {
+            Ok(_) => debug!("CacheWriter::commit - {}", self.body_path.display()),
+            Err(err) => warn!("CacheWriter::commit - {} failed : {}", self.body_path.display(), err),
+        }
+    }
+
+    fn abandon(&mut self) {
+        if self.file.take().is_some() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+impl Drop for CacheWriter {
+    fn drop(&mut self) {
+        self.abandon();
+    }
+}
+
+//NOTE: copies every chunk into the cache while passing it on, a body that is not read to the end is not cached
+pub struct CachingBody<S> {
+    stream: S,
+    writers: Vec<CacheWriter>,
+}
+
+impl<S> CachingBody<S> {
+    pub fn new(stream: S, writers: Vec<CacheWriter>) -> CachingBody<S> {
+        CachingBody {
+            stream: stream,
+            writers: writers,
+        }
+    }
+}
+
+impl<S: Stream<Item=Chunk, Error=hyper::Error>> Stream for CachingBody<S> {
+    type Item = Chunk;
+    type Error = hyper::Error;
+
+    fn poll(&mut self) -> Poll<Option<Chunk>, hyper::Error> {
+        match self.stream.poll() {
+            Ok(Async::Ready(Some(chunk))) => {
+                for writer in self.writers.iter_mut() {
+                    writer.write(&chunk);
+                }
+                Ok(Async::Ready(Option::Some(chunk)))
+            },
+            Ok(Async::Ready(None)) => {
+                for writer in self.writers.drain(..) {
+                    writer.commit();
+                }
+                Ok(Async::Ready(Option::None))
+            },
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(err) => {
+                self.writers.clear();
+                Err(err)
+            },
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::env;
+use std::io;
+
+use hyper::{self,Request,Method,StatusCode,Uri};
+use hyper::client::Response;
+
+use super::super::regex::Regex;
+use configure::{ConnectorConfigure,LoginConfigure,RuleUtils};
+use connector::Connector;
+use connector::futures::{Future,Stream};
+use connector::session::Session;
+use connector::charset::Charset;
+use connector::request_headers::RequestHeaders;
+use connector::request_template::RequestTemplate;
+use result::ResultHandler;
+
+//NOTE: fills the cookie jar of a session by sending a login form, the connectors of the session
+//      send the cookies from then on
+pub struct Login<'a> {
+    configure: &'a LoginConfigure,
+}
+
+impl<'a> Login<'a> {
+    pub fn new(configure: &'a LoginConfigure) -> Login<'a> {
+        Login {
+            configure: configure,
+        }
+    }
+
+    //NOTE: logins are told apart by where the credentials go
+    pub fn key(&self) -> Option<&String> {
+        self.configure.get_action().or(self.configure.get_page())
+    }
+
+    //NOTE: does nothing when the session is already logged in with the same key
+    pub fn run(&self, session: &Session, connector_configure: ConnectorConfigure) -> Result<(), hyper::Error> {
+        let key = match self.key() {
+            Some(key) => key,
+            None => return Err(Login::error("login needs a page or an action".to_owned())),
+        };
+        if session.is_logged_in(key.as_str()) {
+            return Ok(());
+        }
+
+        let request_headers = RequestHeaders::new(connector_configure.get_headers(), Option::None);
+        let mut conn = Connector::with_session(1, connector_configure, session);
+
+        let mut labels = ResultHandler::new(Option::None);
+        let mut source_url = Option::None;
+        match self.configure.get_page() {
+            Some(page) => {
+                let mut request = Request::new(Method::Get, Login::parse_uri(page.as_str())?);
+                request_headers.apply(&mut request, Option::None);
+                let (page_url, content) = Login::fetch(&mut conn, request)?;
+                match self.configure.get_extract() {
+                    Some(extract) => {
+                        let mut extracted = RuleUtils::make_result_handlers(vec![content], extract,
+                                                                            Option::None, page_url.clone());
+                        labels = extracted.pop().unwrap();
+                        //NOTE: a label missing from the page (a csrf token, mostly) would go out empty
+                        let mut unmatched: Vec<String> = labels.get_label_names().into_iter()
+                            .filter(|label_name| labels.get_result(label_name).map_or(true, Vec::is_empty))
+                            .collect();
+                        if !unmatched.is_empty() {
+                            unmatched.sort();
+                            return Err(Login::error(format!("{} not found in the login page {}",
+                                                            unmatched.join(", "), page_url)));
+                        }
+                    },
+                    None => { },
+                }
+                source_url = Option::Some(page_url);
+            },
+            None => { },
+        }
+        self.insert_env_labels(&mut labels)?;
+
+        let request_configure = match self.configure.get_request() {
+            Some(request_configure) => request_configure,
+            None => return Err(Login::error(format!("login to {} needs [login.request]", key))),
+        };
+        let mut request = RequestTemplate::new(Option::Some(request_configure))
+            .make_request(Login::parse_uri(key.as_str())?, Option::Some((&labels, 0)));
+        request_headers.apply(&mut request, source_url.as_ref().map(String::as_str));
+        let (_response_url, content) = Login::fetch(&mut conn, request)?;
+
+        match self.configure.get_success() {
+            Some(success) => {
+                let success_regex = Regex::new(success.as_str())
+                    .map_err(|err| Login::error(format!("success pattern of the login is wrong : {}", err)))?;
+                if !success_regex.is_match(content.as_str()) {
+                    return Err(Login::error(format!("login to {} failed, {} is not found", key, success)));
+                }
+            },
+            None => { },
+        }
+
+        info!("Login::run - logged in to {}", key);
+        session.set_logged_in(key.as_str());
+        Ok(())
+    }
+
+    fn insert_env_labels(&self, labels: &mut ResultHandler) -> Result<(), hyper::Error> {
+        let env_labels = match self.configure.get_env() {
+            Some(env_labels) => env_labels,
+            None => return Ok(()),
+        };
+
+        for (label_name, var_name) in env_labels {
+            match env::var(var_name.as_str()) {
+                Ok(value) => labels.insert_result(label_name.as_str(), vec![value]),
+                Err(_) => return Err(Login::error(format!("{} of the login is not set", var_name))),
+            }
+        }
+        Ok(())
+    }
+
+    //NOTE: the url after redirects and the decoded body of a response that has to be successful
+    fn fetch(conn: &mut Connector, request: Request) -> Result<(String, String), hyper::Error> {
+        let request_url = request.uri().as_ref().to_owned();
+        conn.add_request(request);
+        let mut results = conn.run_request_all(|_index, response: Response| {
+            let status = response.status();
+            let response_url = Connector::get_response_url(&response).unwrap_or(request_url.clone());
+            let header_charset = Connector::get_content_type(&response).charset();
+            response.body().concat2().map(move |body| {
+                let content = Charset::decode(&body, header_charset.as_ref().map(String::as_str), Option::None);
+                (status, response_url, content)
+            })
+        })?;
+
+        let (status, response_url, content) = results.pop().unwrap();
+        match status {
+            status if status.is_success() => Ok((response_url, content)),
+            StatusCode::Unauthorized | StatusCode::Forbidden => {
+                Err(Login::error(format!("login to {} is refused with {}", response_url, status)))
+            },
+            status => Err(Login::error(format!("login page {} answered {}", response_url, status))),
+        }
+    }
+
+    fn parse_uri(url: &str) -> Result<Uri, hyper::Error> {
+        url.parse().map_err(|_| Login::error(format!("{} is not a url", url)))
+    }
+
+    fn error(msg: String) -> hyper::Error {
+        hyper::Error::Io(io::Error::new(io::ErrorKind::PermissionDenied, msg))
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+//NOTE: type/subtype *( ";" name=value ) as in RFC 7231, names are lowercased and quoted values unescaped
+#[derive(Clone, PartialEq, Debug)]
+pub struct MediaType {
+    main_type: String,
+    sub_type: String,
+    parameters: Vec<(String, String)>,
+}
+
+impl MediaType {
+    pub fn parse(content_type: &str) -> Option<MediaType> {
+        let mut sections = MediaType::split_outside_quotes(content_type, ';').into_iter();
+
+        let essence = sections.next().unwrap_or("").trim().to_lowercase();
+        let (main_type, sub_type) = match essence.find('/') {
+            Some(index) => (essence[..index].trim().to_owned(), essence[index + 1..].trim().to_owned()),
+            None => return Option::None,
+        };
+        if !MediaType::is_token(main_type.as_str()) || !MediaType::is_token(sub_type.as_str()) {
+            return Option::None;
+        }
+
+        let mut parameters = Vec::new();
+        for section in sections {
+            let section = section.trim();
+            let (name, value) = match section.find('=') {
+                Some(index) => (section[..index].trim().to_lowercase(), section[index + 1..].trim()),
+                None => continue,
+            };
+            if !MediaType::is_token(name.as_str()) {
+                continue;
+            }
+            let value = if value.starts_with('"') {
+                MediaType::unquote(value)
+            }
+            else {
+                value.to_owned()
+            };
+            if !parameters.iter().any(|&(ref known, _)| *known == name) {
+                parameters.push((name, value));
+            }
+        }
+
+        Option::Some(MediaType {
+            main_type: main_type,
+            sub_type: sub_type,
+            parameters: parameters,
+        })
+    }
+
+    //NOTE: several Content-Type fields or comma joined values can arrive, the last valid one wins as browsers do
+    pub fn parse_header_values<'a, I>(values: I) -> Option<MediaType>
+        where I: Iterator<Item=&'a str> {
+        let mut parsed = Option::None;
+        for value in values {
+            for content_type in MediaType::split_outside_quotes(value, ',') {
+                match MediaType::parse(content_type) {
+                    Some(media_type) => parsed = Option::Some(media_type),
+                    None => { },
+                }
+            }
+        }
+        parsed
+    }
+
+    pub fn main_type(&self) -> &str {
+        self.main_type.as_str()
+    }
+
+    pub fn sub_type(&self) -> &str {
+        self.sub_type.as_str()
+    }
+
+    //NOTE: "image/svg+xml" has the suffix "xml"
+    pub fn suffix(&self) -> Option<&str> {
+        match self.sub_type.rfind('+') {
+            Some(index) => Option::Some(&self.sub_type[index + 1..]),
+            None => Option::None,
+        }
+    }
+
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.main_type, self.sub_type)
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.parameters.iter()
+            .find(|&&(ref known, _)| *known == name)
+            .map(|&(_, ref value)| value.as_str())
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.parameter("charset")
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.main_type == "image"
+    }
+
+    //NOTE: json and xml based application types such as application/xhtml+xml are pages as well
+    pub fn is_text(&self) -> bool {
+        if self.main_type == "text" {
+            return true;
+        }
+        if self.main_type != "application" {
+            return false;
+        }
+
+        match self.sub_type.as_str() {
+            "json" | "xml" | "javascript" | "ecmascript" => true,
+            _ => match self.suffix() {
+                Some("json") | Some("xml") => true,
+                _ => false,
+            },
+        }
+    }
+
+    fn is_token(value: &str) -> bool {
+        !value.is_empty() && value.chars().all(|c| {
+            c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+        })
+    }
+
+    fn unquote(value: &str) -> String {
+        let mut unquoted = String::new();
+        let mut chars = value[1..].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some(escaped) => unquoted.push(escaped),
+                    None => break,
+                },
+                _ => unquoted.push(c),
+            }
+        }
+        unquoted
+    }
+
+    fn split_outside_quotes(value: &str, delimiter: char) -> Vec<&str> {
+        let mut sections = Vec::new();
+        let mut in_quotes = false;
+        let mut escaped = false;
+        let mut start = 0;
+        for (index, c) in value.char_indices() {
+            if escaped {
+                escaped = false;
+                continue;
+            }
+            match c {
+                '\\' if in_quotes => escaped = true,
+                '"' => in_quotes = !in_quotes,
+                _ if c == delimiter && !in_quotes => {
+                    sections.push(&value[start..index]);
+                    start = index + c.len_utf8();
+                },
+                _ => { },
+            }
+        }
+        sections.push(&value[start..]);
+        sections
+    }
+}
+
+pub const UNKNOWN_EXTENSION: &'static str = "unknown";
+
+const DEFAULT_MIME_EXTENSIONS: &'static [(&'static str, &'static str)] = &[
+    ("image/jpeg", "jpg"),
+    ("image/pjpeg", "jpg"),
+    ("image/png", "png"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("image/avif", "avif"),
+    ("image/bmp", "bmp"),
+    ("image/svg+xml", "svg"),
+    ("image/tiff", "tiff"),
+    ("image/x-icon", "ico"),
+    ("image/vnd.microsoft.icon", "ico"),
+    ("text/html", "html"),
+    ("application/xhtml+xml", "xhtml"),
+    ("text/plain", "txt"),
+    ("text/css", "css"),
+    ("text/xml", "xml"),
+    ("application/xml", "xml"),
+    ("application/json", "json"),
+    ("text/javascript", "js"),
+    ("application/javascript", "js"),
+    ("application/pdf", "pdf"),
+    ("application/zip", "zip"),
+    ("application/x-zip-compressed", "zip"),
+    ("video/mp4", "mp4"),
+    ("video/webm", "webm"),
+    ("audio/mpeg", "mp3"),
+];
+
+//NOTE: [extractor.mime_extensions] entries such as "image/heic" = "heic" are added to or replace the defaults
+pub struct MimeExtensions {
+    extensions: HashMap<String, String>,
+}
+
+impl MimeExtensions {
+    pub fn new(overrides: Option<&HashMap<String, String>>) -> MimeExtensions {
+        let mut extensions = HashMap::new();
+        for &(essence, extension) in DEFAULT_MIME_EXTENSIONS {
+            extensions.insert(essence.to_owned(), extension.to_owned());
+        }
+
+        match overrides {
+            Some(overrides) => {
+                for (essence, extension) in overrides {
+                    let extension = extension.trim_start_matches('.');
+                    extensions.insert(essence.trim().to_lowercase(), extension.to_owned());
+                }
+            },
+            None => { },
+        }
+
+        MimeExtensions {
+            extensions: extensions,
+        }
+    }
+
+    //NOTE: unlisted image and text types fall back to the subtype when it can be used as a file extension
+    pub fn extension_of(&self, media_type: &MediaType) -> String {
+        match self.extensions.get(media_type.essence().as_str()) {
+            Some(extension) => return extension.clone(),
+            None => { },
+        }
+
+        let sub_type = media_type.sub_type();
+        let is_plain_sub_type = sub_type.chars().all(|c| c.is_ascii_alphanumeric());
+        if (media_type.is_image() || media_type.main_type() == "text") && is_plain_sub_type {
+            sub_type.to_owned()
+        }
+        else {
+            UNKNOWN_EXTENSION.to_owned()
+        }
+    }
+}
@@ -3,57 +3,104 @@ extern crate tokio_core;
 extern crate core;
 extern crate hyper;
 extern crate hyper_tls;
+extern crate native_tls;
+extern crate tokio_io;
+extern crate rand;
+extern crate encoding_rs;
+extern crate flate2;
+extern crate brotli_decompressor;
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "ios")))]
+extern crate openssl;
 
 pub mod extractor;
 pub mod navigator;
 pub mod connector_utils;
+pub mod retry_policy;
+pub mod timeout;
+pub mod proxy;
+pub mod tls;
+pub mod cookie_jar;
+pub mod charset;
+pub mod decompression;
+pub mod media_type;
+pub mod sniff;
+pub mod robots;
+pub mod session;
+pub mod events;
+pub mod request_headers;
+pub mod request_template;
+pub mod login;
+pub mod auth;
+pub mod http_cache;
+pub mod fixtures;
+pub mod transport;
 #[cfg(test)]
 mod connector_test;
 
-use std::cell::{Ref,RefCell};
+use std::io;
+use std::rc::Rc;
+use std::cell::{Cell,Ref,RefCell};
 use std::vec::Vec;
-use std::collections::VecDeque;
+use std::collections::{HashMap,HashSet,VecDeque};
+use std::time::{Duration,Instant};
 
-use hyper::{Client,Request,Body,Uri};
-use hyper::client::{HttpConnector,Response};
-use self::hyper_tls::HttpsConnector;
-use hyper::header::{Raw};
+use hyper::{Request,Body,Chunk,Uri,Method,StatusCode};
+use hyper::client::Response;
+use hyper::header::{Raw,AcceptEncoding};
 
 use self::futures::future::{IntoFuture};
+use self::futures::Future;
 use self::tokio_core::*;
 
+use configure::ConnectorConfigure;
+use self::retry_policy::RetryPolicy;
+use self::connector_utils::ConnectorUtils;
+use self::timeout::IdleTimeout;
+use self::proxy::ProxySettings;
+use self::auth::{AuthSettings,SecretsCache};
+use self::tls::TlsSettings;
+use self::cookie_jar::CookieJar;
+use self::decompression::{ContentDecoder,DecodingBody,ACCEPT_ENCODING};
+use self::media_type::MediaType;
+use self::robots::{RobotsCache,RobotsRules,RobotsDisallowed,DEFAULT_ROBOTS_USER_AGENT};
+use self::session::Session;
+use self::http_cache::{HttpCache,CachingBody};
+use self::fixtures::{Fixtures,FixturesMode};
+use self::transport::{Transport,HyperTransport};
+
 enum HeaderContentType {
-    Image(String),
-    Text(String),
-    Others(String),
+    Image(MediaType),
+    Text(MediaType),
+    Others(MediaType),
+    Unknown(String),
 }
 
 impl HeaderContentType {
-    //FIXME: standard from_str implementation would be necessary
-    pub fn from_str(content_type: &str) -> HeaderContentType {
-        if content_type.starts_with("image") {
-            let image_format = HeaderContentType::get_image_format(content_type);
-            HeaderContentType::Image(image_format.to_owned())
+    pub fn from_media_type(media_type: MediaType) -> HeaderContentType {
+        if media_type.is_image() {
+            HeaderContentType::Image(media_type)
         }
-        else if content_type.starts_with("text") {
-            let text_format = HeaderContentType::get_text_format(content_type);
-            HeaderContentType::Text(text_format.to_owned())
+        else if media_type.is_text() {
+            HeaderContentType::Text(media_type)
         }
         else {
-            HeaderContentType::Others(content_type.to_owned())
+            HeaderContentType::Others(media_type)
         }
     }
 
-    fn get_image_format<'a>(content_type: &'a str) -> &'a str {
-        //NOTE: image/jpeg, image/png, Application/... can be received
-        let split_collection: Vec<&str> = content_type.split("/").collect();
-        return split_collection[1];
+    pub fn media_type(&self) -> Option<&MediaType> {
+        match self {
+            &HeaderContentType::Image(ref media_type) => Option::Some(media_type),
+            &HeaderContentType::Text(ref media_type) => Option::Some(media_type),
+            &HeaderContentType::Others(ref media_type) => Option::Some(media_type),
+            &HeaderContentType::Unknown(_) => Option::None,
+        }
     }
 
-    fn get_text_format<'a>(content_type: &'a str) -> &'a str {
-        //NOTE: text/html, text/xml, text/css, Application/xml... can be received
-        let split_collection: Vec<&str> = content_type.split("/").collect();
-        return split_collection[1];
+    pub fn charset(&self) -> Option<String> {
+        self.media_type()
+            .and_then(|media_type| media_type.charset())
+            .map(|charset| charset.to_owned())
     }
 }
 
@@ -62,22 +109,161 @@ impl HeaderContentType {
 //    fn response_callback(&self, index: usize, res: Response) -> Stream<Item=hyper::Chunk,Error=hyper::Error>;
 //}
 
+pub const DEFAULT_POOL_SIZE: usize = 20;
+pub const DEFAULT_MAX_REDIRECTS: u32 = 10;
+pub const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 30000;
+pub const DEFAULT_READ_TIMEOUT_MS: u64 = 60000;
+//NOTE: set on every response handed to callbacks, holds the url the response was finally received from
+pub const RESPONSE_URL_HEADER: &'static str = "X-Cruler-Response-Url";
+
 pub struct Connector {
-    core: RefCell<reactor::Core>,
-    client: RefCell<Client<HttpsConnector<HttpConnector>,Body>>,
+    core: Rc<RefCell<reactor::Core>>,
+    //NOTE: the error of a client that can not be made, every request of a run fails with it
+    transport: Result<Rc<Transport>, String>,
     requests: RefCell<VecDeque<Request>>,
+    pool_size: usize,
+    configure: ConnectorConfigure,
+    host_schedule: RefCell<HashMap<String, Instant>>,
+    //NOTE: none when the client is shared through a session, its connect timeout is fixed then
+    connect_timeout: Option<Rc<Cell<Option<Duration>>>>,
+    proxy_settings: Rc<ProxySettings>,
+    //NOTE: the error of a secrets file that can not be loaded, every request of a run fails with it
+    auth_settings: Result<Rc<AuthSettings>, String>,
+    secrets_cache: Rc<SecretsCache>,
+    cookie_jar: Rc<CookieJar>,
+    robots_cache: Rc<RobotsCache>,
 }
 
 impl Connector {
     pub fn new() -> Connector {
-        let core = reactor::Core::new().unwrap();
-        let client = Client::configure().
-            connector(HttpsConnector::new(20, &core.handle()).unwrap()).
-            build(&core.handle());
+        Connector::with_pool_size(DEFAULT_POOL_SIZE)
+    }
+
+    //NOTE: pool_size is the maximum number of requests in flight at once
+    pub fn with_pool_size(pool_size: usize) -> Connector {
+        Connector::with_configure(pool_size, ConnectorConfigure::default())
+    }
+
+    //NOTE: proxy and tls settings are fixed here, set_configure only changes the per request settings
+    pub fn with_configure(pool_size: usize, configure: ConnectorConfigure) -> Connector {
+        Connector::with_transport_of(pool_size, configure, Option::None)
+    }
+
+    //NOTE: every request goes through the given transport instead of the hyper client
+    pub fn with_transport(pool_size: usize, configure: ConnectorConfigure,
+                          transport: Rc<Transport>) -> Connector {
+        Connector::with_transport_of(pool_size, configure, Option::Some(transport))
+    }
+
+    //NOTE: runs on the reactor of the session and through its transport, or through the client the session
+    //      keeps for the proxy and tls settings of configure, so connections stay alive from one connector to the next
+    pub fn with_session(pool_size: usize, configure: ConnectorConfigure, session: &Session) -> Connector {
+        let pool_size = Connector::checked_pool_size(pool_size);
+
+        let core = session.core();
+        let connect_timeout = Connector::connect_timeout_of(&configure);
+        let proxy_settings =
+            Rc::new(ProxySettings::from_configure(configure.get_proxy_configure()));
+        let transport = session.transport_for(&configure, connect_timeout, || {
+            Connector::make_transport(pool_size, &core.borrow().handle(), &configure,
+                                      proxy_settings.clone(), Rc::new(Cell::new(connect_timeout)))
+        });
+
+        let mut conn = Connector::from_parts(pool_size, configure, core, transport, proxy_settings,
+                                             Option::None, session.secrets_cache());
+        conn.set_session(session);
+        conn
+    }
+
+    fn with_transport_of(pool_size: usize, configure: ConnectorConfigure,
+                         transport: Option<Rc<Transport>>) -> Connector {
+        let pool_size = Connector::checked_pool_size(pool_size);
+
+        let core = Rc::new(RefCell::new(reactor::Core::new().unwrap()));
+        let connect_timeout = Rc::new(Cell::new(Connector::connect_timeout_of(&configure)));
+        let proxy_settings =
+            Rc::new(ProxySettings::from_configure(configure.get_proxy_configure()));
+
+        let transport = match transport {
+            Some(transport) => Ok(transport),
+            None => Connector::make_transport(pool_size, &core.borrow().handle(), &configure,
+                                              proxy_settings.clone(), connect_timeout.clone()),
+        };
+        Connector::from_parts(pool_size, configure, core, transport, proxy_settings,
+                              Option::Some(connect_timeout), Rc::new(SecretsCache::new()))
+    }
+
+    fn make_transport(pool_size: usize, handle: &reactor::Handle, configure: &ConnectorConfigure,
+                      proxy_settings: Rc<ProxySettings>,
+                      connect_timeout: Rc<Cell<Option<Duration>>>) -> Result<Rc<Transport>, String> {
+        let tls_settings = TlsSettings::from_configure(configure.get_tls_configure());
+        match HyperTransport::new(pool_size, handle, proxy_settings, &tls_settings, connect_timeout) {
+            Ok(transport) => Ok(Rc::new(transport)),
+            Err(err) => {
+                error!("Connector::make_transport - no client is made : {}", err);
+                Err(err.to_string())
+            },
+        }
+    }
+
+    fn from_parts(pool_size: usize, configure: ConnectorConfigure, core: Rc<RefCell<reactor::Core>>,
+                  transport: Result<Rc<Transport>, String>, proxy_settings: Rc<ProxySettings>,
+                  connect_timeout: Option<Rc<Cell<Option<Duration>>>>, secrets_cache: Rc<SecretsCache>) -> Connector {
+        let auth_settings = AuthSettings::from_configure(&configure, &secrets_cache).map(Rc::new);
+
         Connector {
-            core: RefCell::new(core),
-            client: RefCell::new(client),
+            core: core,
+            transport: transport,
             requests: RefCell::new(VecDeque::new()),
+            pool_size: pool_size,
+            configure: configure,
+            host_schedule: RefCell::new(HashMap::new()),
+            connect_timeout: connect_timeout,
+            proxy_settings: proxy_settings,
+            auth_settings: auth_settings,
+            secrets_cache: secrets_cache,
+            cookie_jar: Rc::new(CookieJar::new()),
+            robots_cache: Rc::new(RobotsCache::new()),
+        }
+    }
+
+    pub fn get_pool_size(&self) -> usize {
+        self.pool_size
+    }
+
+    //NOTE: the connect timeout of a client shared through a session is kept as it was made
+    pub fn set_configure(&mut self, configure: ConnectorConfigure) {
+        match self.connect_timeout {
+            Some(ref connect_timeout) => connect_timeout.set(Connector::connect_timeout_of(&configure)),
+            None => { },
+        }
+        self.auth_settings = AuthSettings::from_configure(&configure, &self.secrets_cache).map(Rc::new);
+        self.configure = configure;
+    }
+
+    pub fn get_configure(&self) -> &ConnectorConfigure {
+        &self.configure
+    }
+
+    //NOTE: connectors given the same jar share one cookie session
+    pub fn set_cookie_jar(&mut self, cookie_jar: Rc<CookieJar>) {
+        self.cookie_jar = cookie_jar;
+    }
+
+    pub fn get_cookie_jar(&self) -> Rc<CookieJar> {
+        self.cookie_jar.clone()
+    }
+
+    pub fn get_transport(&self) -> Option<Rc<Transport>> {
+        self.transport.as_ref().ok().cloned()
+    }
+
+    pub fn set_session(&mut self, session: &Session) {
+        self.cookie_jar = session.cookie_jar();
+        self.robots_cache = session.robots_cache();
+        if !Rc::ptr_eq(&self.secrets_cache, &session.secrets_cache()) {
+            self.secrets_cache = session.secrets_cache();
+            self.auth_settings = AuthSettings::from_configure(&self.configure, &self.secrets_cache).map(Rc::new);
         }
     }
 
@@ -98,6 +284,10 @@ impl Connector {
         self.requests.borrow_mut().clear();
     }
 
+    pub fn take_requests(&mut self) -> VecDeque<Request> {
+        self.requests.borrow_mut().drain(..).collect()
+    }
+
     pub fn requests(&self) -> Ref<VecDeque<Request>> {
         self.requests.borrow()
     }
@@ -114,37 +304,657 @@ impl Connector {
         urls
     }
 
+    //NOTE: fails with the first error, run_request_each keeps the outcome of every request
     pub fn run_request_all<F, B>(&mut self, f: F) -> Result<Vec<B::Item>, hyper::Error>
         where
             F: Fn(usize, Response) -> B,
             B: IntoFuture<Error=::hyper::Error> {
-        use self::futures::future::*;
+        let results = self.run_request_each(f);
+        results.into_iter().collect()
+    }
+
+    //NOTE: the outcome of the request queued at index n is placed at index n
+    pub fn run_request_each<F, B>(&mut self, f: F) -> Vec<Result<B::Item, hyper::Error>>
+        where
+            F: Fn(usize, Response) -> B,
+            B: IntoFuture<Error=::hyper::Error> {
+        use self::futures::Stream;
+        use self::futures::stream;
+        use self::futures::future::{self,Either};
 
         let function = &f;
-        let mut req_futures = Vec::new();
-        let mut requests = self.requests.borrow_mut();
-        let mut pass_index: usize = 0;
-        loop {
-            let pop = requests.pop_front();
-            match pop {
-                Option::None => break,
-                Option::Some(request) => {
-                    let client = self.client.borrow_mut();
-                    let job = client.request(request).and_then(move |res| {
-                        function(pass_index, res)
-                    });
-                    req_futures.push(job);
+        let mut requests: Vec<Request> = self.requests.borrow_mut().drain(..).collect();
+        let (transport, auth_settings) = match (&self.transport, &self.auth_settings) {
+            (&Ok(ref transport), &Ok(ref auth_settings)) => (transport.clone(), auth_settings.clone()),
+            (&Err(ref msg), _) | (_, &Err(ref msg)) => {
+                warn!("Connector::run_request_each - {} requests are not sent : {}", requests.len(), msg);
+                return requests.iter()
+                    .map(|_| Err(hyper::Error::Io(io::Error::new(io::ErrorKind::Other, msg.clone()))))
+                    .collect();
+            },
+        };
+        let transport = &*transport;
+        let auth_settings = &*auth_settings;
+        let fixtures = Fixtures::from_configure(&self.configure);
+        let fixtures = fixtures.as_ref();
+        //NOTE: replay never goes to the network, robots.txt and request delays included
+        let replaying = fixtures.map_or(false, |fixtures| fixtures.mode() == FixturesMode::Replay);
+        let respect_robots = self.configure.get_respect_robots().unwrap_or(true) && !replaying;
+        let unavailable_robots = if respect_robots {
+            self.load_robots(&requests, transport, auth_settings)
+        }
+        else {
+            RobotsCache::new()
+        };
+        let unavailable_robots = &unavailable_robots;
+        let bodies = self.take_bodies(&mut requests);
+        let robots_cache = &*self.robots_cache;
+        let handle = self.core.borrow().handle();
+        let handle = &handle;
+        let retry_policy = RetryPolicy::from_configure(&self.configure);
+        let max_redirects = self.configure.get_max_redirects().unwrap_or(DEFAULT_MAX_REDIRECTS);
+        let read_timeout = Connector::read_timeout_of(&self.configure);
+        let request_timeout = Connector::request_timeout_of(&self.configure);
+        let proxy_settings = &*self.proxy_settings;
+        let cookie_jar = &*self.cookie_jar;
+        let decompress = self.configure.get_decompress().unwrap_or(true);
+        //NOTE: fixtures have to see every real response, so the cache is off while they are on
+        let http_cache = match fixtures {
+            Some(_) => Option::None,
+            None => HttpCache::from_configure(&self.configure),
+        };
+        let http_cache = http_cache.as_ref();
+
+        //NOTE: buffered keeps at most pool_size requests in flight and yields results in queue order
+        let work = stream::iter_ok(requests.into_iter().zip(bodies).enumerate())
+            .map(|(pass_index, (mut request, body))| {
+                let crawl_delay = if respect_robots {
+                    let robots_user_agent = Connector::robots_user_agent_of(&request);
+                    let robots_user_agent = robots_user_agent.as_str();
+                    if !robots_cache.is_allowed(request.uri(), robots_user_agent) ||
+                        !unavailable_robots.is_allowed(request.uri(), robots_user_agent) {
+                        info!("Connector::run_request_each - {} is disallowed by robots.txt, skipped",
+                              request.uri());
+                        let disallowed = RobotsDisallowed::new(request.uri().as_ref());
+                        let err = hyper::Error::Io(io::Error::new(io::ErrorKind::Other, disallowed));
+                        return Either::A(future::ok(Err(err)));
+                    }
+                    robots_cache.crawl_delay(request.uri(), robots_user_agent)
+                }
+                else {
+                    Option::None
+                };
+
+                if decompress && !request.headers().has::<AcceptEncoding>() {
+                    request.headers_mut().set_raw("Accept-Encoding", ACCEPT_ENCODING);
+                }
+                //NOTE: only GET responses are cached, keyed by the url before redirects,
+                //      a range request resumes a download and is neither revalidated nor stored
+                let ranged = request.headers().get_raw("Range").is_some() || request.headers().get_raw("If-Range").is_some();
+                let cache_url = match (http_cache, request.method()) {
+                    (Some(http_cache), &Method::Get) if !ranged => {
+                        Option::Some((http_cache, request.uri().as_ref().to_owned()))
+                    },
+                    _ => Option::None,
+                };
+                let cache_entry = match cache_url {
+                    Some((http_cache, ref url)) => http_cache.lookup(url.as_str()),
+                    None => Option::None,
+                };
+                match cache_entry {
+                    Some(ref cache_entry) => cache_entry.prepare_request(&mut request),
+                    None => { },
+                }
+                let record_key = match fixtures {
+                    Some(fixtures) if fixtures.mode() == FixturesMode::Record => {
+                        Option::Some((fixtures, Fixtures::key_of(&request, body.as_ref().map(Vec::as_slice))))
+                    },
+                    _ => Option::None,
+                };
+
+                let wait = if replaying {
+                    Option::None
+                }
+                else {
+                    self.reserve_host_slot(request.uri(), crawl_delay)
+                };
+                Either::B(Connector::sleep(handle, wait).and_then(move |_| {
+                    let sending = match fixtures {
+                        Some(fixtures) if replaying => {
+                            Either::A(future::result(fixtures.replay(&request, body.as_ref().map(Vec::as_slice))))
+                        },
+                        _ => Either::B(Connector::request_with_redirect(transport, handle, proxy_settings,
+                                                                        auth_settings, cookie_jar, retry_policy,
+                                                                        read_timeout, max_redirects, request, body)),
+                    };
+                    let job = sending
+                        .and_then(move |res| {
+                            match cache_entry {
+                                Some(cache_entry) if res.status() == StatusCode::NotModified => {
+                                    debug!("Connector::run_request_each - {} is not modified, served from the cache",
+                                           Connector::get_response_url(&res).unwrap_or(String::new()));
+                                    return cache_entry.into_response(res);
+                                },
+                                _ => { },
+                            }
+                            Ok(Connector::prepare_body(handle, read_timeout, decompress,
+                                                       cache_url, record_key, res))
+                        }).and_then(move |res| {
+                            function(pass_index, res)
+                        });
+                    Connector::with_deadline(handle, request_timeout, job)
+                }).then(|result| {
+                    Ok::<_, ()>(result)
+                }))
+            })
+            .buffered(self.pool_size)
+            .collect();
+
+        let mut core = self.core.borrow_mut();
+        match core.run(work) {
+            Ok(results) => results,
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn is_robots_disallowed(err: &hyper::Error) -> bool {
+        match err {
+            &hyper::Error::Io(ref io_err) => match io_err.get_ref() {
+                Some(inner) => inner.is::<RobotsDisallowed>(),
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    //NOTE: the product token of the User-Agent header the request goes out with, so that robots.txt
+    //      is matched against the agent the site actually sees
+    fn robots_user_agent_of(request: &Request) -> String {
+        request.headers().get_raw("User-Agent")
+            .and_then(|raw| raw.one())
+            .map(|value| String::from_utf8_lossy(value).trim().to_owned())
+            .and_then(|value| if value.is_empty() { Option::None } else { Option::Some(value) })
+            .unwrap_or(DEFAULT_ROBOTS_USER_AGENT.to_owned())
+    }
+
+    //NOTE: fetches robots.txt of every origin in requests that is not cached yet with the User-Agent
+    //      of its first request, an origin whose robots.txt is unavailable (5xx or unreachable) is
+    //      disallowed for this run only and left out of the cache so the next run asks again
+    fn load_robots(&self, requests: &Vec<Request>, transport: &Transport,
+                   auth_settings: &AuthSettings) -> RobotsCache {
+        use self::futures::Stream;
+        use self::futures::stream;
+
+        let mut origins: Vec<(String, String)> = Vec::new();
+        for request in requests {
+            match RobotsCache::origin_of(request.uri()) {
+                Some(origin) => {
+                    if !self.robots_cache.contains(origin.as_str()) &&
+                        !origins.iter().any(|&(ref known, _)| *known == origin) {
+                        origins.push((origin, Connector::robots_user_agent_of(request)));
+                    }
                 },
+                None => { },
             }
-            pass_index += 1;
         }
+        let unavailable = RobotsCache::new();
+        if origins.is_empty() {
+            return unavailable;
+        }
+
+        let handle = self.core.borrow().handle();
+        let handle = &handle;
+        let retry_policy = RetryPolicy::from_configure(&self.configure);
+        let max_redirects = self.configure.get_max_redirects().unwrap_or(DEFAULT_MAX_REDIRECTS);
+        let read_timeout = Connector::read_timeout_of(&self.configure);
+        let request_timeout = Connector::request_timeout_of(&self.configure);
+        let proxy_settings = &*self.proxy_settings;
+        let cookie_jar = &*self.cookie_jar;
+
+        let work = stream::iter_ok(origins.into_iter())
+            .map(|(origin, user_agent)| {
+                let robots_uri: Uri = format!("{}/robots.txt", origin).parse().unwrap();
+                debug!("Connector::load_robots - {} as {}", robots_uri, user_agent);
+                let mut robots_request = Request::new(Method::Get, robots_uri);
+                robots_request.headers_mut().set_raw("User-Agent", user_agent);
+                let job = Connector::request_with_redirect(transport, handle, proxy_settings,
+                                                           auth_settings, cookie_jar, retry_policy, read_timeout,
+                                                           max_redirects, robots_request, Option::None)
+                    .and_then(move |res| {
+                        let status = res.status();
+                        let res = Connector::prepare_body(handle, read_timeout, false,
+                                                          Option::None, Option::None, res);
+                        res.body().concat2().map(move |content| {
+                            (status, RobotsRules::from_response(status, &content))
+                        })
+                    });
+                Connector::with_deadline(handle, request_timeout, job).then(move |result| {
+                    let loaded = match result {
+                        Ok((status, rules)) => {
+                            let cached = status.is_success() || status.is_client_error();
+                            if !cached {
+                                warn!("Connector::load_robots - robots.txt of {} is unavailable ({}), disallowed for now",
+                                      origin, status);
+                            }
+                            (rules, cached)
+                        },
+                        Err(err) => {
+                            warn!("Connector::load_robots - robots.txt of {} is unreachable, disallowed for now : {}",
+                                  origin, err);
+                            (RobotsRules::disallow_all(), false)
+                        },
+                    };
+                    Ok::<_, ()>((origin, loaded))
+                })
+            })
+            .buffer_unordered(self.pool_size)
+            .collect();
+
+        let mut core = self.core.borrow_mut();
+        match core.run(work) {
+            Ok(loaded) => {
+                for (origin, (rules, cached)) in loaded {
+                    if cached {
+                        self.robots_cache.insert(origin, rules);
+                    }
+                    else {
+                        unavailable.insert(origin, rules);
+                    }
+                }
+            },
+            Err(_) => { },
+        }
+        unavailable
+    }
+
+    //NOTE: reserves the next free slot of the host and returns how long the request has to wait for it,
+    //      Crawl-delay of robots.txt is used when it is longer than the configured interval
+    fn reserve_host_slot(&self, uri: &Uri, crawl_delay: Option<Duration>) -> Option<Duration> {
+        let interval = match (self.configure.get_request_interval(), crawl_delay) {
+            (Some(interval), Some(crawl_delay)) if crawl_delay > interval => crawl_delay,
+            (Some(interval), _) => interval,
+            (None, Some(crawl_delay)) => crawl_delay,
+            (None, None) => return Option::None,
+        };
+        let host = match uri.host() {
+            Some(host) => host,
+            None => return Option::None,
+        };
+
+        let now = Instant::now();
+        let mut host_schedule = self.host_schedule.borrow_mut();
+        let slot = match host_schedule.get(host) {
+            Some(next_slot) if *next_slot > now => *next_slot,
+            _ => now,
+        };
+        host_schedule.insert(host.to_owned(), slot + interval);
+
+        if slot > now {
+            debug!("Connector::reserve_host_slot - {} waits {:?}", host, slot - now);
+            Option::Some(slot - now)
+        }
+        else {
+            Option::None
+        }
+    }
+
+    fn request_with_redirect<'c>(transport: &'c Transport,
+                                 handle: &'c reactor::Handle,
+                                 proxy_settings: &'c ProxySettings,
+                                 auth_settings: &'c AuthSettings,
+                                 cookie_jar: &'c CookieJar,
+                                 retry_policy: RetryPolicy,
+                                 read_timeout: Option<Duration>,
+                                 max_redirects: u32,
+                                 request: Request,
+                                 body: Option<Vec<u8>>) -> Box<Future<Item=Response, Error=hyper::Error> + 'c> {
+        use std::io;
+        use self::futures::future::{self,Loop};
+
+        let mut visited = HashSet::new();
+        visited.insert(request.uri().as_ref().to_owned());
+
+        let work = future::loop_fn((request, body, visited, 0), move |(request, body, mut visited, hops)| {
+            let template = Connector::duplicate_request(&request);
+            Connector::request_with_retry(transport, handle, proxy_settings, auth_settings, cookie_jar,
+                                          retry_policy, read_timeout, request, body.clone()).and_then(move |mut response| {
+                let location = if max_redirects > 0 {
+                    Connector::get_redirect_location(&response, template.uri())
+                }
+                else {
+                    Option::None
+                };
+
+                let location = match location {
+                    Some(location) => location,
+                    None => {
+                        let response_url = template.uri().as_ref().to_owned();
+                        response.headers_mut().set_raw(RESPONSE_URL_HEADER, response_url);
+                        return Ok(Loop::Break(response));
+                    },
+                };
+
+                if hops >= max_redirects {
+                    let msg = format!("too many redirects from {}", template.uri());
+                    return Err(hyper::Error::Io(io::Error::new(io::ErrorKind::Other, msg)));
+                }
+                if !visited.insert(location.as_ref().to_owned()) {
+                    let msg = format!("redirect loop detected at {}", location);
+                    return Err(hyper::Error::Io(io::Error::new(io::ErrorKind::Other, msg)));
+                }
+                debug!("Connector::request_with_redirect - {} -> {}", template.uri(), location);
+
+                //NOTE: 303 always turns into GET, and so do 301 and 302 after POST as browsers do
+                let method = match response.status() {
+                    StatusCode::SeeOther => Method::Get,
+                    StatusCode::MovedPermanently | StatusCode::Found
+                        if *template.method() == Method::Post => Method::Get,
+                    _ => template.method().clone(),
+                };
+                //NOTE: the body only goes along when the method is kept, as 307 and 308 require
+                let body = if method == *template.method() { body } else { Option::None };
+                let mut redirected = Request::new(method, location);
+                redirected.headers_mut().extend(template.headers().iter());
+                if body.is_none() {
+                    redirected.headers_mut().remove_raw("Content-Type");
+                    redirected.headers_mut().remove_raw("Content-Length");
+                }
+                Ok(Loop::Continue((redirected, body, visited, hops + 1)))
+            })
+        });
+        Box::new(work)
+    }
+
+    fn get_redirect_location(response: &Response, request_uri: &Uri) -> Option<Uri> {
+        use hyper::header::Location;
+
+        match response.status() {
+            StatusCode::MovedPermanently | StatusCode::Found | StatusCode::SeeOther |
+            StatusCode::TemporaryRedirect | StatusCode::PermanentRedirect => { },
+            _ => return Option::None,
+        }
+
+        match response.headers().get::<Location>() {
+            Some(location) => ConnectorUtils::resolve_uri(request_uri, location),
+            None => {
+                warn!("Connector::get_redirect_location - {} redirected without location",
+                      request_uri);
+                Option::None
+            },
+        }
+    }
+
+    pub fn get_response_url(response: &Response) -> Option<String> {
+        match Connector::get_header_raw_value(response, RESPONSE_URL_HEADER) {
+            Some(raw_value) => match raw_value.one() {
+                Some(value) => String::from_utf8(value.to_vec()).ok(),
+                None => Option::None,
+            },
+            None => Option::None,
+        }
+    }
+
+    //NOTE: read_timeout also bounds the wait for the response headers, so a server that accepts and
+    //      never answers fails with Error::Timeout like a stalled body does
+    fn request_with_retry<'c>(transport: &'c Transport,
+                              handle: &'c reactor::Handle,
+                              proxy_settings: &'c ProxySettings,
+                              auth_settings: &'c AuthSettings,
+                              cookie_jar: &'c CookieJar,
+                              retry_policy: RetryPolicy,
+                              read_timeout: Option<Duration>,
+                              request: Request,
+                              body: Option<Vec<u8>>) -> Box<Future<Item=Response, Error=hyper::Error> + 'c> {
+        use self::futures::future::{self,Loop,Either};
+
+        if retry_policy.max_attempts() <= 1 || !retry_policy.retries_method(request.method()) {
+            let uri = request.uri().clone();
+            let mut request = request;
+            Connector::attach_body(&mut request, body.as_ref());
+            proxy_settings.prepare_request(&mut request);
+            auth_settings.prepare_request(&mut request);
+            cookie_jar.prepare_request(&mut request);
+            let sending = Connector::with_deadline(handle, read_timeout,
+                                                   transport.request(handle, request).map_err(Connector::normalize_error));
+            return Box::new(sending.map(move |response| {
+                cookie_jar.store_response(&uri, &response);
+                response
+            }));
+        }
+
+        let work = future::loop_fn(1, move |attempt| {
+            let uri = request.uri().clone();
+            let mut sending_request = Connector::duplicate_request(&request);
+            Connector::attach_body(&mut sending_request, body.as_ref());
+            proxy_settings.prepare_request(&mut sending_request);
+            auth_settings.prepare_request(&mut sending_request);
+            cookie_jar.prepare_request(&mut sending_request);
+            let sending = Connector::with_deadline(handle, read_timeout,
+                                                   transport.request(handle, sending_request).map_err(Connector::normalize_error));
+            sending.then(move |result| {
+                match result {
+                    Ok(ref response) => cookie_jar.store_response(&uri, response),
+                    Err(_) => { },
+                }
+                match retry_policy.retry_delay(attempt, &result) {
+                    Some(delay) => {
+                        info!("Connector::request_with_retry - retry {} after {:?} (attempt {})",
+                              uri, delay, attempt);
+                        Either::A(Connector::sleep(handle, Some(delay)).map(move |_| {
+                            Loop::Continue(attempt + 1)
+                        }))
+                    },
+                    None => Either::B(future::result(result.map(Loop::Break))),
+                }
+            })
+        });
+        Box::new(work)
+    }
+
+    //NOTE: connect timeouts surface as io errors of TimedOut kind, they are reported as Error::Timeout
+    fn normalize_error(err: hyper::Error) -> hyper::Error {
+        use std::io::ErrorKind;
+
+        match err {
+            hyper::Error::Io(ref io_err) if io_err.kind() == ErrorKind::TimedOut => {
+                return hyper::Error::Timeout;
+            },
+            _ => { },
+        }
+        err
+    }
+
+    //NOTE: the body is pumped through a channel so that a stalled read fails with Error::Timeout,
+    //      a compressed body reaches the callbacks already decoded and a cacheable or recorded one is
+    //      copied to disk
+    fn prepare_body(handle: &reactor::Handle, read_timeout: Option<Duration>,
+                    decompress: bool, cache_url: Option<(&HttpCache, String)>,
+                    record_key: Option<(&Fixtures, String)>, response: Response) -> Response {
+        use self::futures::{Sink,Stream};
+
+        let decoder = match response.headers().get_raw("Content-Encoding").and_then(|raw| raw.one()) {
+            Some(content_encoding) if decompress => {
+                ContentDecoder::from_content_encoding(&String::from_utf8_lossy(content_encoding))
+            },
+            _ => Option::None,
+        };
+        if read_timeout.is_none() && decoder.is_none() && cache_url.is_none() && record_key.is_none() {
+            return response;
+        }
+
+        let status = response.status();
+        let mut headers = response.headers().clone();
+        let mut prepared: Box<Stream<Item=Chunk, Error=hyper::Error>> = Box::new(response.body());
+        match read_timeout {
+            Some(read_timeout) => prepared = Box::new(IdleTimeout::new(prepared, read_timeout, handle)),
+            None => { },
+        }
+        match decoder {
+            Some(decoder) => {
+                headers.remove_raw("Content-Encoding");
+                headers.remove_raw("Content-Length");
+                prepared = Box::new(DecodingBody::new(prepared, decoder));
+            },
+            None => { },
+        }
+        let mut writers = Vec::new();
+        match cache_url.and_then(|(http_cache, url)| http_cache.writer_for(url.as_str(), status, &headers)) {
+            Some(cache_writer) => writers.push(cache_writer),
+            None => { },
+        }
+        match record_key.and_then(|(fixtures, key)| fixtures.recorder_for(key.as_str(), status, &headers)) {
+            Some(recorder) => writers.push(recorder),
+            None => { },
+        }
+        if !writers.is_empty() {
+            prepared = Box::new(CachingBody::new(prepared, writers));
+        }
+
+        let (sender, body) = Body::pair();
+        let pumping = sender.send_all(prepared.then(|chunk| Ok(chunk)))
+            .map(|_| ())
+            .map_err(|_| ());
+        handle.spawn(pumping);
+
+        Response::new().with_status(status).with_headers(headers).with_body(body)
+    }
+
+    fn with_deadline<'c, J>(handle: &reactor::Handle, request_timeout: Option<Duration>,
+                            job: J) -> Box<Future<Item=J::Item, Error=hyper::Error> + 'c>
+        where J: Future<Error=hyper::Error> + 'c {
+        use self::futures::future::{self,Either};
+
+        let deadline = match request_timeout {
+            Some(request_timeout) => {
+                Either::A(Connector::sleep(handle, Some(request_timeout)).then(|_| {
+                    Err(hyper::Error::Timeout)
+                }))
+            },
+            None => Either::B(future::empty()),
+        };
+
+        Box::new(job.select(deadline)
+            .map(|(item, _deadline)| item)
+            .map_err(|(err, _deadline)| err))
+    }
+
+    //NOTE: connection_pool_size of configure wins over fallback, DEFAULT_POOL_SIZE when neither is set
+    //      or the one that is set is less than 1
+    pub fn pool_size_of(configure: &ConnectorConfigure, fallback: Option<i64>) -> usize {
+        match configure.get_connection_pool_size().or(fallback) {
+            Some(pool_size) if pool_size > 0 => pool_size as usize,
+            Some(pool_size) => {
+                warn!("Connector::pool_size_of - connection_pool_size has to be at least 1, {} is ignored and {} is used",
+                      pool_size, DEFAULT_POOL_SIZE);
+                DEFAULT_POOL_SIZE
+            },
+            None => DEFAULT_POOL_SIZE,
+        }
+    }
+
+    //NOTE: no request would ever be sent with 0 in flight
+    fn checked_pool_size(pool_size: usize) -> usize {
+        if pool_size == 0 {
+            warn!("Connector::checked_pool_size - pool_size 0 sends nothing, 1 is used");
+            1
+        }
+        else {
+            pool_size
+        }
+    }
+
+    fn connect_timeout_of(configure: &ConnectorConfigure) -> Option<Duration> {
+        let timeout_ms = configure.get_connect_timeout_ms().unwrap_or(DEFAULT_CONNECT_TIMEOUT_MS);
+        Connector::timeout_from_ms(timeout_ms)
+    }
+
+    fn read_timeout_of(configure: &ConnectorConfigure) -> Option<Duration> {
+        let timeout_ms = configure.get_read_timeout_ms().unwrap_or(DEFAULT_READ_TIMEOUT_MS);
+        Connector::timeout_from_ms(timeout_ms)
+    }
 
-        let work = futures::future::join_all(req_futures);
+    fn request_timeout_of(configure: &ConnectorConfigure) -> Option<Duration> {
+        let timeout_ms = configure.get_request_timeout_ms().unwrap_or(0);
+        Connector::timeout_from_ms(timeout_ms)
+    }
+
+    //NOTE: 0 means no timeout
+    fn timeout_from_ms(timeout_ms: u64) -> Option<Duration> {
+        if timeout_ms == 0 {
+            Option::None
+        }
+        else {
+            Option::Some(Duration::from_millis(timeout_ms))
+        }
+    }
+
+    //NOTE: the body is not copied, it is kept aside by take_bodies and attached to every attempt
+    fn duplicate_request(request: &Request) -> Request {
+        let mut duplicated = Request::new(request.method().clone(), request.uri().clone());
+        duplicated.set_version(request.version());
+        duplicated.headers_mut().extend(request.headers().iter());
+        duplicated
+    }
+
+    //NOTE: bodies of queued requests are read on the reactor before they are sent, so that a streamed one
+    //      fed from the same reactor is not waited for while it is blocked
+    fn take_bodies(&self, requests: &mut Vec<Request>) -> Vec<Option<Vec<u8>>> {
+        use self::futures::Stream;
+        use self::futures::future::{self,Either};
+
+        let reading: Vec<_> = requests.iter_mut().map(|request| {
+            let uri = request.uri().clone();
+            match request.body_mut().take() {
+                Some(body) => Either::A(body.concat2().then(move |content| {
+                    match content {
+                        Ok(content) => Ok::<_, ()>(Option::Some(content.to_vec())),
+                        Err(err) => {
+                            warn!("Connector::take_bodies - body of {} is not readable, sent without it : {}",
+                                  uri, err);
+                            Ok(Option::None)
+                        },
+                    }
+                })),
+                None => Either::B(future::ok(Option::None)),
+            }
+        }).collect();
 
         let mut core = self.core.borrow_mut();
-        core.run(work)
+        core.run(future::join_all(reading)).unwrap()
     }
 
+    fn attach_body(request: &mut Request, body: Option<&Vec<u8>>) {
+        use hyper::header::ContentLength;
+
+        match body {
+            Some(body) => {
+                request.headers_mut().set(ContentLength(body.len() as u64));
+                request.set_body(body.clone());
+            },
+            None => { },
+        }
+    }
+
+    fn sleep(handle: &reactor::Handle, wait: Option<Duration>)
+        -> Box<Future<Item=(), Error=hyper::Error>> {
+        use self::futures::future;
+
+        let wait = match wait {
+            Some(wait) => wait,
+            None => return Box::new(future::ok(())),
+        };
+
+        match reactor::Timeout::new(wait, handle) {
+            Ok(timeout) => Box::new(timeout.map_err(hyper::Error::Io)),
+            Err(err) => Box::new(future::err(hyper::Error::Io(err))),
+        }
+    }
+
+    pub fn get_content_length(response: &Response) -> Option<u64> {
+        use hyper::header::ContentLength;
+
+        response.headers().get::<ContentLength>().map(|content_length| content_length.0)
+    }
 
     pub fn get_header_raw_value(response: &Response, key_name: &str) -> Option<Raw> {
         let headers = response.headers();
@@ -160,34 +970,22 @@ impl Connector {
         Some(owned)
     }
 
-    fn get_raw_cookies(response: &Response) -> Option<Raw> {
-        Connector::get_header_raw_value(response, "Set-Cookie")
-    }
-
 
     fn get_content_type(response: &Response) -> HeaderContentType {
-        let raw_value = Connector::get_header_raw_value(response, "content-type");
-
-        let content_type: Raw = match raw_value {
+        let raw_value = match Connector::get_header_raw_value(response, "content-type") {
             Option::Some(content) => content,
             Option::None => {
-                return HeaderContentType::Others("no content-type".to_owned());
+                return HeaderContentType::Unknown("no content-type".to_owned());
             }
         };
 
-        if content_type.len() > 1 {
-            return HeaderContentType::Others("content-type is more than 2".to_string());
+        let values: Vec<String> = raw_value.iter()
+            .map(|value| String::from_utf8_lossy(value).into_owned())
+            .collect();
+        match MediaType::parse_header_values(values.iter().map(String::as_str)) {
+            Some(media_type) => HeaderContentType::from_media_type(media_type),
+            None => HeaderContentType::Unknown(format!("invalid content-type : {}", values.join(", "))),
         }
-
-        for value in content_type.iter() {
-            let content = String::from_utf8(value.to_vec()).expect("Found invalid UTF-8");
-
-            let header_content_type = HeaderContentType::from_str(content.as_str());
-            return header_content_type;
-        }
-
-        return HeaderContentType::Others("unknown".to_owned());
     }
 
-
 }
@@ -3,10 +3,13 @@ use std::collections::{HashMap,VecDeque};
 use super::hyper;
 use super::futures::*;
 
-use hyper::header::{Raw};
-use hyper::{Request,Method};
-
 use connector::{Connector,HeaderContentType};
+use connector::session::Session;
+use connector::request_headers::RequestHeaders;
+use connector::request_template::RequestTemplate;
+use connector::login::Login;
+use connector::events::{CrawlEvent,EventSink,EventStream};
+use connector::charset::Charset;
 use configure::*;
 use result::*;
 
@@ -17,15 +20,49 @@ use self::specific_pager::{Ordering,SpecificPager,DefaultPager,NaverWebtoonPager
 
 pub struct Navigator<'a> {
     rules: &'a NavigationRules,
+    connector_config: Option<&'a ConnectorConfigure>,
+    login: Option<&'a LoginConfigure>,
+    session: Session,
+    events: EventSink,
 }
 
 impl<'a> Navigator<'a> {
-    pub fn new(rule_config: &'a NavigationRules) -> Navigator<'a> {
+    pub fn new(rule_config: &'a NavigationRules,
+               connector_config: Option<&'a ConnectorConfigure>) -> Navigator<'a> {
+        Navigator::with_session(rule_config, connector_config, Session::new())
+    }
+
+    pub fn with_session(rule_config: &'a NavigationRules,
+                        connector_config: Option<&'a ConnectorConfigure>,
+                        session: Session) -> Navigator<'a> {
         Navigator {
             rules: rule_config,
+            connector_config: connector_config,
+            login: Option::None,
+            session: session,
+            events: EventSink::default(),
         }
     }
 
+    //NOTE: navigates every rule on a thread of its own, see Extractor::stream_all
+    pub fn stream_all(rules: NavigationRules, configure: Configure) -> EventStream {
+        EventStream::spawn(move |events| {
+            let mut navigator = Navigator::new(&rules, configure.get_connector_configure());
+            navigator.set_login(configure.get_login_configure());
+            navigator.set_events(events);
+            navigator.navigate_all();
+        })
+    }
+
+    pub fn set_events(&mut self, events: EventSink) {
+        self.events = events;
+    }
+
+    //NOTE: the global [login], done once per session before the first rule that is not logged in by its own
+    pub fn set_login(&mut self, login: Option<&'a LoginConfigure>) {
+        self.login = login;
+    }
+
     //FIXME: navigate_all for testing
     pub fn navigate_all(&self) {
         let navigation_rules = self.rules.navigation();
@@ -77,70 +114,129 @@ impl<'a> Navigator<'a> {
     }
 
     pub fn navigate(&self, rule: &UnitNavigationRule) -> VecDeque<String> {
-        let mut conn = Connector::new();
-        let mut pager = Navigator::get_pager(rule);
+        match self.log_in(rule) {
+            Err(err) => {
+                error!("Navigator::navigate - {} is not navigated : {}", rule.name(), err);
+                self.events.send(CrawlEvent::Error {
+                    rule: rule.name().clone(),
+                    url: Option::None,
+                    message: err.to_string(),
+                });
+                return VecDeque::new();
+            },
+            Ok(_) => { },
+        }
+        let mut conn = self.make_connector(rule);
+        let request_headers = self.make_request_headers(rule);
+        let mut pager = self.get_pager(rule);
         let entry_uri = pager.get_entry_uri(rule.entry().as_str());
         pager.set_as_requested(entry_uri.as_ref());
-        conn.add_request(Request::new(Method::Get, entry_uri));
+        let mut entry_request = RequestTemplate::new(rule.request()).make_request(entry_uri, Option::None);
+        request_headers.apply(&mut entry_request, Option::None);
+        conn.add_request(entry_request);
 
+        let pager_template = RequestTemplate::new(rule.pager().and_then(|pager| pager.request()));
         let mut extracted_nav_links = VecDeque::new();
         while pager.has_next_request() {
-            let response_result =
-                Navigator::run_request(&mut conn, rule);
+            //NOTE: nothing left to request, e.g. every pager page failed
+            if conn.get_requests_count() == 0 {
+                break;
+            }
+
+            let request_urls = conn.request_urls();
+            let response_results =
+                Navigator::run_request(&mut conn, rule, &self.events);
             conn.clear_requests();
 
-            match response_result {
-                Result::Ok(navigation_result_handlers) => {
-                    let mut in_page_links =
-                        self.get_navigation_links_in_page(&mut conn, &mut pager,
-                                                          &navigation_result_handlers);
-                    Navigator::merge_vec(&mut extracted_nav_links,
-                                         &mut in_page_links, pager.ordering());
-                },
-                Result::Err(err) => {
-                    info!("Navigator::navigate - err : {}", err);
-                    continue;
-                },
+            for (index, response_result) in response_results.into_iter().enumerate() {
+                match response_result {
+                    Result::Ok(navigation_result_handler) => {
+                        let mut in_page_links =
+                            self.get_navigation_links_in_page(&mut conn, &mut pager, &request_headers,
+                                                              &pager_template, &navigation_result_handler);
+                        Navigator::merge_vec(&mut extracted_nav_links,
+                                             &mut in_page_links, pager.ordering());
+                    },
+                    //NOTE: robots.txt skips are logged by the connector
+                    Result::Err(ref err) if Connector::is_robots_disallowed(err) => { },
+                    Result::Err(err) => {
+                        warn!("Navigator::navigate - {} failed : {}", request_urls[index], err);
+                        self.events.send(CrawlEvent::Error {
+                            rule: rule.name().clone(),
+                            url: Option::Some(request_urls[index].clone()),
+                            message: err.to_string(),
+                        });
+                    },
+                }
             }
         }
 
         extracted_nav_links
     }
 
-    fn get_navigation_links_in_page(&self, conn: &mut Connector, pager: &mut Box<SpecificPager>,
-                                    handlers: &Vec<NavigationResultHandler>) -> VecDeque<String> {
-        let mut links = VecDeque::new();
-        for navigation_result_handler in handlers {
-            let pager_result =
-                navigation_result_handler.pager_result();
-            match pager_result {
-                Option::Some(pager_result_handlers) => {
-                    let mut requests =
-                        pager.make_next_requests(pager_result_handlers);
-                    conn.add_requests(&mut requests);
-                },
-                Option::None => { },
-            }
+    //NOTE: the login of the rule wins over the global one
+    fn log_in(&self, rule: &UnitNavigationRule) -> Result<(), hyper::Error> {
+        match rule.login().or(self.login) {
+            Some(login) => Login::new(login).run(&self.session, self.merge_connector_configure(rule)),
+            None => Ok(()),
+        }
+    }
+
+    fn make_connector(&self, rule: &UnitNavigationRule) -> Connector {
+        let connector_config = self.merge_connector_configure(rule);
+        let pool_size = Connector::pool_size_of(&connector_config, Option::None);
+
+        Connector::with_session(pool_size, connector_config, &self.session)
+    }
+
+    fn merge_connector_configure(&self, rule: &UnitNavigationRule) -> ConnectorConfigure {
+        match self.connector_config {
+            Some(connector_config) => connector_config.merge(rule.connector()),
+            None => ConnectorConfigure::default().merge(rule.connector()),
+        }
+    }
 
-            let extracted_results = navigation_result_handler.extracted_results();
-            let mut nav_results =
-                pager.collect_ordered_result(ReservedLabel::Collect,
-                                             extracted_results);
+    fn make_request_headers(&self, rule: &UnitNavigationRule) -> RequestHeaders {
+        let connector_config = self.merge_connector_configure(rule);
+        RequestHeaders::new(connector_config.get_headers(), rule.headers())
+    }
 
-            links.append(&mut nav_results);
+    fn get_navigation_links_in_page(&self, conn: &mut Connector, pager: &mut Box<SpecificPager>,
+                                    request_headers: &RequestHeaders, pager_template: &RequestTemplate,
+                                    navigation_result_handler: &NavigationResultHandler) -> VecDeque<String> {
+        let pager_result =
+            navigation_result_handler.pager_result();
+        match pager_result {
+            Option::Some(pager_result_handlers) => {
+                let mut requests =
+                    pager.make_next_requests(pager_result_handlers, pager_template);
+                let source_url = navigation_result_handler.source_url();
+                for request in requests.iter_mut() {
+                    request_headers.apply(request, Option::Some(source_url));
+                }
+                conn.add_requests(&mut requests);
+            },
+            Option::None => { },
         }
-        links
+
+        let extracted_results = navigation_result_handler.extracted_results();
+        pager.collect_ordered_result(ReservedLabel::Collect, extracted_results)
     }
 
-    fn run_request(conn: &mut Connector, rule: &UnitNavigationRule)
-        -> Result<Vec<NavigationResultHandler>, hyper::Error> {
+    fn run_request(conn: &mut Connector, rule: &UnitNavigationRule, events: &EventSink)
+        -> Vec<Result<NavigationResultHandler, hyper::Error>> {
         let request_urls = conn.request_urls();
         let request_urls = &request_urls;
-        conn.run_request_all(|index, response| {
-            let source_url = &request_urls[index];
+        conn.run_request_each(|index, response| {
+            //NOTE: relative links resolve against the url after redirects
+            let source_url = match Connector::get_response_url(&response) {
+                Some(response_url) => response_url,
+                None => request_urls[index].clone(),
+            };
             let unit_response_handler =
-                UnitNavigationRuleResponseHandler::new(source_url.clone(), rule);
+                UnitNavigationRuleResponseHandler::new(source_url, rule);
             let header_type = Connector::get_content_type(&response);
+            let header_charset = header_type.charset();
             let will_be_okay = match header_type {
                 HeaderContentType::Text(_header) => {
                     true
@@ -150,8 +246,6 @@ impl<'a> Navigator<'a> {
                 },
             };
 
-            let raw_cookies: Option<Raw> = Connector::get_raw_cookies(&response);
-
             response.body().fold(Vec::new(), move |mut v, chunk| {
                 if !will_be_okay {
                     return future::failed(hyper::Error::Header);
@@ -159,44 +253,49 @@ impl<'a> Navigator<'a> {
                 v.extend(&chunk[..]);
                 future::ok::<_, hyper::Error>(v)
             }).and_then(move |chunks| {
-                let body_content = match String::from_utf8(chunks) {
-                    Ok(body) => body,
-                    Err(err) => {
-                        return future::failed(hyper::Error::Utf8(err.utf8_error()));
-                    }
-                };
+                let body_content =
+                    Charset::decode(&chunks, header_charset.as_ref().map(String::as_str),
+                                    rule.encoding().map(String::as_str));
 
                 let part_contents =
                     unit_response_handler.part_from_content((&body_content).as_str());
                 let extract_contents: Vec<ResultHandler> =
-                    unit_response_handler.extract_from_content_part(part_contents,
-                                                                    raw_cookies.clone());
+                    unit_response_handler.extract_from_content_part(part_contents);
 
                 let pager_part_contents =
                     unit_response_handler.part_with_pager((&body_content).as_str());
                 let pager_results: Vec<ResultHandler> =
-                    unit_response_handler.extract_from_pager_part(pager_part_contents,
-                                                                  raw_cookies.clone());
+                    unit_response_handler.extract_from_pager_part(pager_part_contents);
+
+                let source_url = unit_response_handler.source_url.as_str();
+                events.send(CrawlEvent::PageFetched {
+                    rule: rule.name().clone(),
+                    url: source_url.to_owned(),
+                });
+                for extract_content in &extract_contents {
+                    events.send(CrawlEvent::item_of(rule.name().as_str(), source_url, extract_content));
+                }
 
                 let navigation_result_handler =
-                    NavigationResultHandler::new(extract_contents,
+                    NavigationResultHandler::new(unit_response_handler.source_url.clone(),
+                                                 extract_contents,
                                                  Option::Some(pager_results));
 
-                future::ok(navigation_result_handler)
+                future::ok::<_, hyper::Error>(navigation_result_handler)
             })
         })
     }
 
-    fn get_pager(rule: &UnitNavigationRule) -> Box<SpecificPager> {
+    fn get_pager(&self, rule: &UnitNavigationRule) -> Box<SpecificPager> {
         match rule.pager() {
             Some(pager) => {
                 let pager_name = pager.pager().as_str();
                 match pager_name {
                     "naver-webtoon" => Box::new(NaverWebtoonPager::new()),
-                    _ => Box::new(DefaultPager::new()),
+                    _ => Box::new(DefaultPager::with_session(&self.session)),
                 }
             },
-            None => Box::new(DefaultPager::new()),
+            None => Box::new(DefaultPager::with_session(&self.session)),
         }
     }
 }
@@ -230,23 +329,20 @@ impl<'a> UnitNavigationRuleResponseHandler<'a> {
         part_contents
     }
 
-    fn extract_from_content_part(&self,
-                                 part_contents: Vec<String>,
-                                 raw_cookies: Option<Raw>) -> Vec<ResultHandler> {
+    fn extract_from_content_part(&self, part_contents: Vec<String>) -> Vec<ResultHandler> {
         let rule = self.rule;
         let extract_rule = rule.extract();
 
-        self.extract_from_parts(extract_rule, part_contents, raw_cookies)
+        self.extract_from_parts(extract_rule, part_contents)
     }
 
     fn extract_from_parts(&self, extract_rule: Option<&String>,
-                          part_contents: Vec<String>,
-                          raw_cookies: Option<Raw>) -> Vec<ResultHandler> {
+                          part_contents: Vec<String>) -> Vec<ResultHandler> {
         let extract_contents: Vec<ResultHandler> = match extract_rule {
             Option::Some(extract_exp) => {
                 RuleUtils::make_result_handlers(part_contents, extract_exp,
                                                 Option::None,
-                                                self.source_url.clone(), raw_cookies)
+                                                self.source_url.clone())
             },
             Option::None => {
                 let empty_vec = Vec::new();
@@ -278,9 +374,7 @@ impl<'a> UnitNavigationRuleResponseHandler<'a> {
         part_content
     }
 
-    fn extract_from_pager_part(&self,
-                               part_contents: Vec<String>,
-                               raw_cookies: Option<Raw>) -> Vec<ResultHandler> {
+    fn extract_from_pager_part(&self, part_contents: Vec<String>) -> Vec<ResultHandler> {
         let pager_rule: &PagerRule = match self.rule.pager() {
             Option::Some(part_rule) => part_rule,
             Option::None => {
@@ -289,25 +383,31 @@ impl<'a> UnitNavigationRuleResponseHandler<'a> {
             }
         };
         let extract_rule = pager_rule.extract();
-        self.extract_from_parts(extract_rule, part_contents, raw_cookies)
+        self.extract_from_parts(extract_rule, part_contents)
     }
 
 }
 
 struct NavigationResultHandler {
+    source_url: String,
     extracted_results: Vec<ResultHandler>,
     pager_result: Option<Vec<ResultHandler>>,
 }
 
 impl NavigationResultHandler {
-    pub fn new(extracted_results: Vec<ResultHandler>,
+    pub fn new(source_url: String, extracted_results: Vec<ResultHandler>,
                pager_result: Option<Vec<ResultHandler>>) -> NavigationResultHandler {
         NavigationResultHandler {
+            source_url: source_url,
             extracted_results: extracted_results,
             pager_result: pager_result,
         }
     }
 
+    pub fn source_url(&self) -> &str {
+        self.source_url.as_str()
+    }
+
     pub fn extracted_results(&self) -> &Vec<ResultHandler> {
         &self.extracted_results
     }
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use super::super::super::toml;
 use super::super::super::configure::*;
 
@@ -34,6 +36,11 @@ pub struct UnitNavigationRule {
     extract: Option<String>,
     procedure: Option<ProcedureRule>,
     pager: Option<PagerRule>,
+    connector: Option<ConnectorConfigure>,
+    encoding: Option<String>,
+    headers: Option<BTreeMap<String, String>>,
+    request: Option<RequestConfigure>,
+    login: Option<LoginConfigure>,
 }
 
 impl Clone for UnitNavigationRule {
@@ -68,6 +75,41 @@ impl Clone for UnitNavigationRule {
             },
             None => None,
         };
+        let connector = match self.connector() {
+            Some(ref_connector) => {
+                let connector = ref_connector.clone();
+                Some(connector)
+            },
+            None => None,
+        };
+        let encoding = match self.encoding() {
+            Some(ref_encoding) => {
+                let encoding = ref_encoding.clone();
+                Some(encoding)
+            },
+            None => None,
+        };
+        let headers = match self.headers() {
+            Some(ref_headers) => {
+                let headers = ref_headers.clone();
+                Some(headers)
+            },
+            None => None,
+        };
+        let request = match self.request() {
+            Some(ref_request) => {
+                let request = ref_request.clone();
+                Some(request)
+            },
+            None => None,
+        };
+        let login = match self.login() {
+            Some(ref_login) => {
+                let login = ref_login.clone();
+                Some(login)
+            },
+            None => None,
+        };
 
         UnitNavigationRule {
             name: name,
@@ -76,6 +118,11 @@ impl Clone for UnitNavigationRule {
             extract: extract,
             procedure: procedure,
             pager: pager,
+            connector: connector,
+            encoding: encoding,
+            headers: headers,
+            request: request,
+            login: login,
         }
     }
 }
@@ -106,6 +153,30 @@ impl UnitNavigationRule {
     pub fn pager(&self) -> Option<&PagerRule> {
         self.pager.as_ref()
     }
+
+    pub fn connector(&self) -> Option<&ConnectorConfigure> {
+        self.connector.as_ref()
+    }
+
+    //NOTE: overrides the charset detected from the response, e.g. "euc-kr" or "shift_jis"
+    pub fn encoding(&self) -> Option<&String> {
+        self.encoding.as_ref()
+    }
+
+    //NOTE: added to [connector.headers], e.g. Referer = "{source_url}"
+    pub fn headers(&self) -> Option<&BTreeMap<String, String>> {
+        self.headers.as_ref()
+    }
+
+    //NOTE: how the entry request is sent, e.g. a search form that only answers POST
+    pub fn request(&self) -> Option<&RequestConfigure> {
+        self.request.as_ref()
+    }
+
+    //NOTE: used instead of the global [login]
+    pub fn login(&self) -> Option<&LoginConfigure> {
+        self.login.as_ref()
+    }
 }
 
 #[derive(Deserialize)]
@@ -154,6 +225,7 @@ pub struct PagerRule {
     pager: String,
     parts: Option<Vec<String>>,
     extract: Option<String>,
+    request: Option<RequestConfigure>,
 }
 
 impl Clone for PagerRule {
@@ -173,11 +245,19 @@ impl Clone for PagerRule {
             },
             None => None,
         };
+        let request = match self.request() {
+            Some(ref_request) => {
+                let request = ref_request.clone();
+                Some(request)
+            },
+            None => None,
+        };
 
         PagerRule {
             pager: pager,
             parts: parts,
             extract: extract,
+            request: request,
         }
     }
 }
@@ -186,6 +266,11 @@ impl PagerRule {
     pub fn pager(&self) -> &String {
         &self.pager
     }
+
+    //NOTE: how the next pages are requested, {label} takes the pager result of each link
+    pub fn request(&self) -> Option<&RequestConfigure> {
+        self.request.as_ref()
+    }
 }
 
 impl RuleConfigure for PagerRule {
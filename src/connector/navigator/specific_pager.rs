@@ -1,8 +1,11 @@
 use std::cell::{RefCell};
 use std::collections::{HashSet,HashMap,VecDeque};
 
-use hyper::{Uri,Request,Method};
-use connector::{Connector};
+use hyper::{Uri,Request};
+use connector::{Connector,DEFAULT_POOL_SIZE};
+use connector::session::Session;
+use connector::request_template::RequestTemplate;
+use configure::ConnectorConfigure;
 use connector::core::str::FromStr;
 use connector::connector_utils::ConnectorUtils;
 
@@ -18,7 +21,8 @@ pub trait SpecificPager {
     fn is_requested(&self, link: &str) -> bool;
     fn set_as_requested(&self, link: &str);
     fn has_next_request(&self) -> bool;
-    fn make_next_requests(&mut self, pager_results: &Vec<ResultHandler>) -> VecDeque<Request>;
+    fn make_next_requests(&mut self, pager_results: &Vec<ResultHandler>,
+                          request_template: &RequestTemplate) -> VecDeque<Request>;
     fn collect_ordered_result(&self, label: ReservedLabel,
                               result_handlers: &Vec<ResultHandler>) -> VecDeque<String>;
     fn ordering(&self) -> Ordering;
@@ -60,7 +64,8 @@ impl SpecificPager for NaverWebtoonPager {
         self.next_request_available
     }
 
-    fn make_next_requests(&mut self, pager_results: &Vec<ResultHandler>) -> VecDeque<Request> {
+    fn make_next_requests(&mut self, pager_results: &Vec<ResultHandler>,
+                          request_template: &RequestTemplate) -> VecDeque<Request> {
         let mut requests = VecDeque::new();
 
         for result_handler in pager_results {
@@ -69,12 +74,13 @@ impl SpecificPager for NaverWebtoonPager {
                 result_handler.get_result(link_label.as_str());
             match link_result {
                 Option::Some(links) => {
-                    for link in links {
+                    for (link_index, link) in links.iter().enumerate() {
                         if !self.is_requested(link.as_str()) {
                             let link_string = NaverWebtoonPager::decode_escape_char(link.to_owned());
                             let requestable_uri =
                                 result_handler.make_requestable_uri(link_string.as_str());
-                            requests.push_back(Request::new(Method::Get, requestable_uri));
+                            requests.push_back(request_template.make_request(requestable_uri,
+                                                                             Option::Some((result_handler, link_index))));
 
                             self.insert_history(link.as_str());
 
@@ -235,7 +241,8 @@ impl SpecificPager for DefaultPager {
         true
     }
 
-    fn make_next_requests(&mut self, pager_results: &Vec<ResultHandler>) -> VecDeque<Request> {
+    fn make_next_requests(&mut self, pager_results: &Vec<ResultHandler>,
+                          request_template: &RequestTemplate) -> VecDeque<Request> {
         let requests = VecDeque::new();
 
         requests
@@ -269,7 +276,11 @@ impl SpecificPager for DefaultPager {
 
 impl DefaultPager {
     pub fn new() -> DefaultPager {
-        let mut conn = Connector::new();
+        DefaultPager::with_session(&Session::new())
+    }
+
+    pub fn with_session(session: &Session) -> DefaultPager {
+        let conn = Connector::with_session(DEFAULT_POOL_SIZE, ConnectorConfigure::default(), session);
 
         DefaultPager {
             conn: RefCell::new(conn),
@@ -0,0 +1,414 @@
+use std::io;
+use std::env;
+use std::rc::Rc;
+
+use hyper::{Uri,Request};
+use hyper::client::{HttpConnector,Service};
+use hyper::header::{ProxyAuthorization,Basic};
+
+use super::futures::{Future};
+use super::futures::future::{self,Loop};
+use super::tokio_core::net::TcpStream;
+use super::tokio_io::io::{write_all,read_exact};
+
+use configure::ProxyConfigure;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ProxyScheme {
+    Http,
+    Socks5,
+}
+
+#[derive(Clone, Debug)]
+pub struct Proxy {
+    scheme: ProxyScheme,
+    host: String,
+    port: u16,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl Proxy {
+    //NOTE: scheme://[username[:password]@]host[:port], http is assumed when the scheme is missing
+    pub fn parse(url: &str) -> Option<Proxy> {
+        let url = url.trim();
+        if url.is_empty() {
+            return Option::None;
+        }
+
+        let (scheme, rest) = match url.find("://") {
+            Some(index) => (&url[..index], &url[index + 3..]),
+            None => ("http", url),
+        };
+        let (scheme, default_port) = match scheme.to_lowercase().as_str() {
+            "http" | "https" => (ProxyScheme::Http, 8080),
+            "socks5" | "socks5h" => (ProxyScheme::Socks5, 1080),
+            _ => {
+                warn!("Proxy::parse - unsupported proxy scheme : {}", scheme);
+                return Option::None;
+            },
+        };
+
+        let authority = match rest.find('/') {
+            Some(index) => &rest[..index],
+            None => rest,
+        };
+        let (userinfo, host_port) = match authority.rfind('@') {
+            Some(index) => (Option::Some(&authority[..index]), &authority[index + 1..]),
+            None => (Option::None, authority),
+        };
+        let (username, password) = match userinfo {
+            Some(userinfo) => match userinfo.find(':') {
+                Some(index) => (Option::Some(userinfo[..index].to_owned()),
+                                Option::Some(userinfo[index + 1..].to_owned())),
+                None => (Option::Some(userinfo.to_owned()), Option::None),
+            },
+            None => (Option::None, Option::None),
+        };
+        let (host, port) = match host_port.rfind(':') {
+            Some(index) => match host_port[index + 1..].parse::<u16>() {
+                Ok(port) => (&host_port[..index], port),
+                Err(_) => return Option::None,
+            },
+            None => (host_port, default_port),
+        };
+        if host.is_empty() {
+            return Option::None;
+        }
+
+        Option::Some(Proxy {
+            scheme: scheme,
+            host: host.to_owned(),
+            port: port,
+            username: username,
+            password: password,
+        })
+    }
+
+    pub fn scheme(&self) -> &ProxyScheme {
+        &self.scheme
+    }
+
+    pub fn host(&self) -> &str {
+        self.host.as_str()
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    fn set_credentials_if_missing(&mut self, username: Option<&String>, password: Option<&String>) {
+        if self.username.is_none() {
+            self.username = username.cloned();
+            self.password = password.cloned();
+        }
+    }
+
+    pub fn basic_authorization(&self) -> Option<String> {
+        match self.username {
+            Some(ref username) => {
+                let authorization = ProxyAuthorization(Basic {
+                    username: username.clone(),
+                    password: self.password.clone(),
+                });
+                Option::Some(authorization.to_string())
+            },
+            None => Option::None,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct ProxySettings {
+    http: Option<Proxy>,
+    https: Option<Proxy>,
+    no_proxy: Vec<String>,
+}
+
+impl ProxySettings {
+    //NOTE: values in the configure win over HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY
+    pub fn from_configure(configure: Option<&ProxyConfigure>) -> ProxySettings {
+        let all_proxy = ProxySettings::env_value("ALL_PROXY");
+
+        let (http, https, no_proxy, username, password) = match configure {
+            Some(configure) => (configure.get_http().cloned(),
+                                configure.get_https().cloned(),
+                                configure.get_no_proxy().cloned(),
+                                configure.get_username(),
+                                configure.get_password()),
+            None => (Option::None, Option::None, Option::None, Option::None, Option::None),
+        };
+
+        let http = http.or(ProxySettings::env_value("HTTP_PROXY")).or(all_proxy.clone());
+        let https = https.or(ProxySettings::env_value("HTTPS_PROXY")).or(all_proxy);
+        let no_proxy = match no_proxy {
+            Some(no_proxy) => no_proxy,
+            None => match ProxySettings::env_value("NO_PROXY") {
+                Some(no_proxy) => no_proxy.split(',').map(|host| host.trim().to_owned()).collect(),
+                None => Vec::new(),
+            },
+        };
+
+        let parse = |url: Option<String>| {
+            match url.and_then(|url| Proxy::parse(url.as_str())) {
+                Some(mut proxy) => {
+                    proxy.set_credentials_if_missing(username, password);
+                    Option::Some(proxy)
+                },
+                None => Option::None,
+            }
+        };
+
+        ProxySettings {
+            http: parse(http),
+            https: parse(https),
+            no_proxy: no_proxy.into_iter().filter(|host| !host.is_empty()).collect(),
+        }
+    }
+
+    fn env_value(name: &str) -> Option<String> {
+        match env::var(name) {
+            Ok(value) => Option::Some(value),
+            Err(_) => env::var(name.to_lowercase()).ok(),
+        }
+    }
+
+    pub fn proxy_for(&self, uri: &Uri) -> Option<&Proxy> {
+        let host = match uri.host() {
+            Some(host) => host,
+            None => return Option::None,
+        };
+        if self.is_no_proxy(host) {
+            return Option::None;
+        }
+
+        match uri.scheme() {
+            Some("https") => self.https.as_ref(),
+            _ => self.http.as_ref(),
+        }
+    }
+
+    //NOTE: "*" matches every host, "example.com" and ".example.com" match the domain and its subdomains
+    pub fn is_no_proxy(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        for no_proxy in &self.no_proxy {
+            let no_proxy = no_proxy.to_lowercase();
+            if no_proxy == "*" {
+                return true;
+            }
+
+            let domain = no_proxy.trim_start_matches('.');
+            if host == domain || host.ends_with(format!(".{}", domain).as_str()) {
+                return true;
+            }
+        }
+        false
+    }
+
+    //NOTE: plain http goes to an http proxy in absolute form instead of through a tunnel
+    pub fn prepare_request(&self, request: &mut Request) {
+        let authorization = match self.proxy_for(request.uri()) {
+            Some(proxy) if *proxy.scheme() == ProxyScheme::Http &&
+                request.uri().scheme() != Some("https") => proxy.basic_authorization(),
+            _ => return,
+        };
+
+        request.set_proxy(true);
+        match authorization {
+            Some(authorization) => {
+                request.headers_mut().set_raw("Proxy-Authorization", authorization);
+            },
+            None => { },
+        }
+    }
+}
+
+//NOTE: sits below the tls layer, so https is tunneled through CONNECT or SOCKS5 and tls runs end to end
+#[derive(Clone)]
+pub struct ProxyConnector {
+    http: HttpConnector,
+    settings: Rc<ProxySettings>,
+}
+
+impl ProxyConnector {
+    pub fn new(http: HttpConnector, settings: Rc<ProxySettings>) -> ProxyConnector {
+        ProxyConnector {
+            http: http,
+            settings: settings,
+        }
+    }
+
+    fn http_connect(tcp: TcpStream, host: String, port: u16, authorization: Option<String>)
+        -> Box<Future<Item=TcpStream, Error=io::Error>> {
+        let mut connect = format!("CONNECT {0}:{1} HTTP/1.1\r\nHost: {0}:{1}\r\n", host, port);
+        match authorization {
+            Some(authorization) => {
+                connect.push_str(format!("Proxy-Authorization: {}\r\n", authorization).as_str());
+            },
+            None => { },
+        }
+        connect.push_str("\r\n");
+
+        let work = write_all(tcp, connect.into_bytes()).and_then(|(tcp, _)| {
+            ProxyConnector::read_response_head(tcp)
+        }).and_then(|(tcp, head)| {
+            let head = String::from_utf8_lossy(&head).into_owned();
+            let status_line = head.lines().next().unwrap_or("");
+            let is_established = status_line.starts_with("HTTP/1.") &&
+                status_line.split_whitespace().nth(1) == Some("200");
+            if is_established {
+                Ok(tcp)
+            }
+            else {
+                let msg = format!("proxy CONNECT failed : {}", status_line);
+                Err(io::Error::new(io::ErrorKind::Other, msg))
+            }
+        });
+        Box::new(work)
+    }
+
+    //NOTE: reads byte by byte so that nothing after the head is consumed from the tunnel
+    fn read_response_head(tcp: TcpStream) -> Box<Future<Item=(TcpStream, Vec<u8>), Error=io::Error>> {
+        const MAX_HEAD_SIZE: usize = 8192;
+
+        let work = future::loop_fn((tcp, Vec::new()), |(tcp, mut head)| {
+            read_exact(tcp, [0u8; 1]).and_then(move |(tcp, byte)| {
+                head.push(byte[0]);
+                if head.ends_with(b"\r\n\r\n") {
+                    Ok(Loop::Break((tcp, head)))
+                }
+                else if head.len() > MAX_HEAD_SIZE {
+                    Err(io::Error::new(io::ErrorKind::InvalidData, "proxy response head is too large"))
+                }
+                else {
+                    Ok(Loop::Continue((tcp, head)))
+                }
+            })
+        });
+        Box::new(work)
+    }
+
+    fn socks5_connect(tcp: TcpStream, host: String, port: u16,
+                      username: Option<String>, password: Option<String>)
+        -> Box<Future<Item=TcpStream, Error=io::Error>> {
+        let greeting = if username.is_some() { vec![5, 2, 0, 2] } else { vec![5, 1, 0] };
+
+        let work = write_all(tcp, greeting).and_then(|(tcp, _)| {
+            read_exact(tcp, [0u8; 2])
+        }).and_then(move |(tcp, reply)| -> Box<Future<Item=TcpStream, Error=io::Error>> {
+            if reply[0] != 5 {
+                return Box::new(future::err(ProxyConnector::socks5_error("invalid greeting reply")));
+            }
+            match (reply[1], username) {
+                (0, _) => Box::new(future::ok(tcp)),
+                (2, Some(username)) => {
+                    let password = password.unwrap_or_default();
+                    ProxyConnector::socks5_authenticate(tcp, username, password)
+                },
+                _ => Box::new(future::err(ProxyConnector::socks5_error("no acceptable auth method"))),
+            }
+        }).and_then(move |tcp| {
+            let host_bytes = host.into_bytes();
+            let mut connect = vec![5, 1, 0, 3, host_bytes.len() as u8];
+            connect.extend(host_bytes);
+            connect.push((port >> 8) as u8);
+            connect.push((port & 0xff) as u8);
+            write_all(tcp, connect)
+        }).and_then(|(tcp, _)| {
+            read_exact(tcp, [0u8; 5])
+        }).and_then(|(tcp, reply)| {
+            if reply[0] != 5 || reply[1] != 0 {
+                let msg = format!("socks5 connect failed with reply {}", reply[1]);
+                return Err(ProxyConnector::socks5_error(msg.as_str()));
+            }
+            //NOTE: the bound address is not used, but it has to be drained from the stream
+            let remaining = match reply[3] {
+                1 => 4 - 1 + 2,
+                3 => reply[4] as usize + 2,
+                4 => 16 - 1 + 2,
+                _ => return Err(ProxyConnector::socks5_error("unknown address type")),
+            };
+            Ok((tcp, remaining))
+        }).and_then(|(tcp, remaining)| {
+            read_exact(tcp, vec![0u8; remaining]).map(|(tcp, _)| tcp)
+        });
+        Box::new(work)
+    }
+
+    fn socks5_authenticate(tcp: TcpStream, username: String, password: String)
+        -> Box<Future<Item=TcpStream, Error=io::Error>> {
+        let mut auth = vec![1, username.len() as u8];
+        auth.extend(username.into_bytes());
+        auth.push(password.len() as u8);
+        auth.extend(password.into_bytes());
+
+        let work = write_all(tcp, auth).and_then(|(tcp, _)| {
+            read_exact(tcp, [0u8; 2])
+        }).and_then(|(tcp, reply)| {
+            if reply[1] != 0 {
+                return Err(ProxyConnector::socks5_error("authentication failed"));
+            }
+            Ok(tcp)
+        });
+        Box::new(work)
+    }
+
+    fn socks5_error(msg: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::Other, format!("socks5 proxy : {}", msg))
+    }
+}
+
+impl Service for ProxyConnector {
+    type Request = Uri;
+    type Response = TcpStream;
+    type Error = io::Error;
+    type Future = Box<Future<Item=TcpStream, Error=io::Error>>;
+
+    fn call(&self, uri: Uri) -> Self::Future {
+        let proxy = match self.settings.proxy_for(&uri) {
+            Some(proxy) => proxy.clone(),
+            None => return Box::new(self.http.call(uri)),
+        };
+
+        let is_https = uri.scheme() == Some("https");
+        let host = match uri.host() {
+            Some(host) => host.to_owned(),
+            None => {
+                return Box::new(future::err(io::Error::new(io::ErrorKind::InvalidInput,
+                                                           "invalid url, missing host")));
+            },
+        };
+        let port = match uri.port() {
+            Some(port) => port,
+            None => if is_https { 443 } else { 80 },
+        };
+
+        let proxy_url = format!("http://{}:{}", proxy.host(), proxy.port());
+        let proxy_uri: Uri = match proxy_url.parse() {
+            Ok(proxy_uri) => proxy_uri,
+            Err(_) => {
+                return Box::new(future::err(io::Error::new(io::ErrorKind::InvalidInput,
+                                                           "invalid proxy address")));
+            },
+        };
+        debug!("ProxyConnector::call - {}:{} through {}", host, port, proxy_url);
+
+        let connecting = self.http.call(proxy_uri);
+        match *proxy.scheme() {
+            ProxyScheme::Http if !is_https => Box::new(connecting),
+            ProxyScheme::Http => {
+                let authorization = proxy.basic_authorization();
+                Box::new(connecting.and_then(move |tcp| {
+                    ProxyConnector::http_connect(tcp, host, port, authorization)
+                }))
+            },
+            ProxyScheme::Socks5 => {
+                let username = proxy.username.clone();
+                let password = proxy.password.clone();
+                Box::new(connecting.and_then(move |tcp| {
+                    ProxyConnector::socks5_connect(tcp, host, port, username, password)
+                }))
+            },
+        }
+    }
+}
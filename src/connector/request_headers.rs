@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use hyper::{Uri,Request};
+
+use connector::robots::RobotsCache;
+
+//NOTE: headers set on every request of a rule, values may hold {url}, {host}, {origin} and {source_url}
+pub struct RequestHeaders {
+    templates: Vec<(String, String)>,
+}
+
+impl RequestHeaders {
+    //NOTE: names are compared case-insensitively and the overrides of a rule win over the defaults
+    pub fn new(defaults: Option<&BTreeMap<String, String>>,
+               overrides: Option<&BTreeMap<String, String>>) -> RequestHeaders {
+        let mut templates: Vec<(String, String)> = Vec::new();
+        for headers in defaults.iter().chain(overrides.iter()) {
+            for (name, value) in headers.iter() {
+                let name = name.trim();
+                templates.retain(|&(ref existing, _)| !existing.eq_ignore_ascii_case(name));
+                templates.push((name.to_owned(), value.clone()));
+            }
+        }
+
+        RequestHeaders {
+            templates: templates,
+        }
+    }
+
+    //NOTE: source_url is the page the link of the request was found on, entry requests have none
+    pub fn apply(&self, request: &mut Request, source_url: Option<&str>) {
+        let uri = request.uri().clone();
+        for &(ref name, ref template) in &self.templates {
+            match RequestHeaders::render(template.as_str(), &uri, source_url) {
+                Some(value) => request.headers_mut().set_raw(name.clone(), value),
+                None => {
+                    debug!("RequestHeaders::apply - {} is not set for {}, no source url", name, uri);
+                },
+            }
+        }
+    }
+
+    //NOTE: a value that needs {source_url} is left out when there is no source url
+    pub fn render(template: &str, uri: &Uri, source_url: Option<&str>) -> Option<String> {
+        let mut rendered = template.replace("{url}", uri.as_ref());
+        if rendered.contains("{host}") {
+            rendered = rendered.replace("{host}", uri.host().unwrap_or(""));
+        }
+        if rendered.contains("{origin}") {
+            let origin = RobotsCache::origin_of(uri).unwrap_or(String::new());
+            rendered = rendered.replace("{origin}", origin.as_str());
+        }
+        if rendered.contains("{source_url}") {
+            match source_url {
+                Some(source_url) => rendered = rendered.replace("{source_url}", source_url),
+                None => return Option::None,
+            }
+        }
+        Option::Some(rendered)
+    }
+}
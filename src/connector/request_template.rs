@@ -0,0 +1,203 @@
+use std::str::FromStr;
+
+use hyper::{Uri,Request,Method};
+use hyper::header::ContentLength;
+
+use super::super::toml;
+use configure::RequestConfigure;
+use result::ResultHandler;
+
+const FORM_CONTENT_TYPE: &'static str = "application/x-www-form-urlencoded";
+const JSON_CONTENT_TYPE: &'static str = "application/json";
+
+enum BodyTemplate {
+    Form(Vec<(String, toml::Value)>),
+    Json(toml::Value),
+    Raw(String),
+}
+
+//NOTE: builds the requests of a rule, a rule without [request] gets plain GET requests
+pub struct RequestTemplate {
+    method: Method,
+    content_type: Option<String>,
+    body: Option<BodyTemplate>,
+}
+
+impl RequestTemplate {
+    pub fn new(configure: Option<&RequestConfigure>) -> RequestTemplate {
+        let configure = match configure {
+            Some(configure) => configure,
+            None => return RequestTemplate::get(),
+        };
+
+        let method = match configure.get_method() {
+            Some(method) => match Method::from_str(method.trim().to_uppercase().as_str()) {
+                Ok(method) => method,
+                Err(err) => {
+                    warn!("RequestTemplate::new - {} is not a method, GET is used : {}", method, err);
+                    Method::Get
+                },
+            },
+            None if configure.get_body().is_some() => Method::Post,
+            None => Method::Get,
+        };
+
+        let media_type = match configure.get_content_type().map(|content_type| content_type.trim()) {
+            Some("form") | None => FORM_CONTENT_TYPE.to_owned(),
+            Some("json") => JSON_CONTENT_TYPE.to_owned(),
+            Some(content_type) => content_type.to_owned(),
+        };
+        let body = match configure.get_body() {
+            Some(&toml::Value::String(ref raw)) => Option::Some(BodyTemplate::Raw(raw.clone())),
+            Some(&toml::Value::Table(ref table)) if media_type.contains("json") => {
+                Option::Some(BodyTemplate::Json(toml::Value::Table(table.clone())))
+            },
+            Some(&toml::Value::Table(ref table)) => {
+                let fields = table.iter().map(|(name, value)| (name.clone(), value.clone())).collect();
+                Option::Some(BodyTemplate::Form(fields))
+            },
+            Some(_) => {
+                warn!("RequestTemplate::new - body has to be a table or a string, no body is sent");
+                Option::None
+            },
+            None => Option::None,
+        };
+
+        RequestTemplate {
+            method: method,
+            content_type: body.as_ref().map(|_| media_type),
+            body: body,
+        }
+    }
+
+    pub fn get() -> RequestTemplate {
+        RequestTemplate {
+            method: Method::Get,
+            content_type: Option::None,
+            body: Option::None,
+        }
+    }
+
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    //NOTE: labels are the result and the index of the link the request is made for, entry requests have none
+    pub fn make_request(&self, uri: Uri, labels: Option<(&ResultHandler, usize)>) -> Request {
+        let mut request = Request::new(self.method.clone(), uri);
+        let body = match self.body {
+            Some(BodyTemplate::Form(ref fields)) => RequestTemplate::encode_form(fields, labels),
+            Some(BodyTemplate::Json(ref value)) => RequestTemplate::encode_json(value, labels),
+            Some(BodyTemplate::Raw(ref raw)) => RequestTemplate::render(raw.as_str(), labels),
+            None => return request,
+        };
+
+        match self.content_type {
+            Some(ref content_type) => request.headers_mut().set_raw("Content-Type", content_type.clone()),
+            None => { },
+        }
+        request.headers_mut().set(ContentLength(body.len() as u64));
+        request.set_body(body.into_bytes());
+        request
+    }
+
+    //NOTE: {label} takes the value at the index of the link, or the first one when the label has fewer,
+    //      braces that do not name a label of the result are left as they are
+    pub fn render(template: &str, labels: Option<(&ResultHandler, usize)>) -> String {
+        let (result_handler, index) = match labels {
+            Some(labels) => labels,
+            None => return template.to_owned(),
+        };
+
+        let mut rendered = template.to_owned();
+        for label_name in result_handler.get_label_names() {
+            let placeholder = format!("{{{}}}", label_name);
+            if !rendered.contains(placeholder.as_str()) {
+                continue;
+            }
+            let values = result_handler.get_result(label_name.as_str()).unwrap();
+            let value = values.get(index).or(values.first()).map_or("", String::as_str);
+            rendered = rendered.replace(placeholder.as_str(), value);
+        }
+        rendered
+    }
+
+    //NOTE: an array gives the field once per value
+    fn encode_form(fields: &Vec<(String, toml::Value)>, labels: Option<(&ResultHandler, usize)>) -> String {
+        let mut pairs = Vec::new();
+        for &(ref name, ref value) in fields {
+            let values = match value {
+                &toml::Value::Array(ref values) => values.iter().collect(),
+                value => vec![value],
+            };
+            for value in values {
+                let value = match value {
+                    &toml::Value::String(ref value) => RequestTemplate::render(value.as_str(), labels),
+                    &toml::Value::Table(_) | &toml::Value::Array(_) => {
+                        warn!("RequestTemplate::encode_form - {} can not be sent in a form, skipped", name);
+                        continue;
+                    },
+                    value => value.to_string(),
+                };
+                pairs.push(format!("{}={}", RequestTemplate::encode_form_component(name.as_str()),
+                                   RequestTemplate::encode_form_component(value.as_str())));
+            }
+        }
+        pairs.join("&")
+    }
+
+    fn encode_form_component(component: &str) -> String {
+        let mut encoded = String::new();
+        for byte in component.bytes() {
+            match byte {
+                byte if byte.is_ascii_alphanumeric() => encoded.push(byte as char),
+                b'-' | b'.' | b'_' | b'*' => encoded.push(byte as char),
+                b' ' => encoded.push('+'),
+                _ => encoded.push_str(format!("%{:02X}", byte).as_str()),
+            }
+        }
+        encoded
+    }
+
+    fn encode_json(value: &toml::Value, labels: Option<(&ResultHandler, usize)>) -> String {
+        match value {
+            &toml::Value::String(ref value) => {
+                RequestTemplate::encode_json_string(RequestTemplate::render(value.as_str(), labels).as_str())
+            },
+            &toml::Value::Integer(value) => value.to_string(),
+            &toml::Value::Float(value) => value.to_string(),
+            &toml::Value::Boolean(value) => value.to_string(),
+            &toml::Value::Datetime(ref value) => RequestTemplate::encode_json_string(value.to_string().as_str()),
+            &toml::Value::Array(ref values) => {
+                let values: Vec<String> = values.iter()
+                    .map(|value| RequestTemplate::encode_json(value, labels))
+                    .collect();
+                format!("[{}]", values.join(","))
+            },
+            &toml::Value::Table(ref table) => {
+                let members: Vec<String> = table.iter()
+                    .map(|(name, value)| format!("{}:{}", RequestTemplate::encode_json_string(name.as_str()),
+                                                 RequestTemplate::encode_json(value, labels)))
+                    .collect();
+                format!("{{{}}}", members.join(","))
+            },
+        }
+    }
+
+    fn encode_json_string(value: &str) -> String {
+        let mut encoded = String::from("\"");
+        for ch in value.chars() {
+            match ch {
+                '"' => encoded.push_str("\\\""),
+                '\\' => encoded.push_str("\\\\"),
+                '\n' => encoded.push_str("\\n"),
+                '\r' => encoded.push_str("\\r"),
+                '\t' => encoded.push_str("\\t"),
+                ch if (ch as u32) < 0x20 => encoded.push_str(format!("\\u{:04x}", ch as u32).as_str()),
+                ch => encoded.push(ch),
+            }
+        }
+        encoded.push('"');
+        encoded
+    }
+}
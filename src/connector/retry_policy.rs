@@ -0,0 +1,130 @@
+use std::time::{Duration,SystemTime};
+
+use hyper::{StatusCode,Error,Method};
+use hyper::client::Response;
+use hyper::header::RetryAfter;
+
+use super::rand;
+use configure::ConnectorConfigure;
+
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+pub const DEFAULT_BASE_DELAY_MS: u64 = 500;
+pub const DEFAULT_MAX_DELAY_MS: u64 = 30000;
+pub const DEFAULT_JITTER_MS: u64 = 250;
+
+#[derive(Clone, Copy)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay_ms: u64,
+    max_delay_ms: u64,
+    jitter_ms: u64,
+    retry_post: bool,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, base_delay_ms: u64,
+               max_delay_ms: u64, jitter_ms: u64) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: if max_attempts == 0 { 1 } else { max_attempts },
+            base_delay_ms: base_delay_ms,
+            max_delay_ms: max_delay_ms,
+            jitter_ms: jitter_ms,
+            retry_post: false,
+        }
+    }
+
+    pub fn from_configure(configure: &ConnectorConfigure) -> RetryPolicy {
+        let mut policy = RetryPolicy::new(configure.get_max_attempts().unwrap_or(DEFAULT_MAX_ATTEMPTS),
+                                          configure.get_retry_base_delay_ms().unwrap_or(DEFAULT_BASE_DELAY_MS),
+                                          configure.get_retry_max_delay_ms().unwrap_or(DEFAULT_MAX_DELAY_MS),
+                                          configure.get_retry_jitter_ms().unwrap_or(DEFAULT_JITTER_MS));
+        policy.retry_post = configure.get_retry_post().unwrap_or(false);
+        policy
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    //NOTE: only idempotent methods are sent again, a POST only with retry_post = true
+    pub fn retries_method(&self, method: &Method) -> bool {
+        match *method {
+            Method::Get | Method::Head | Method::Put | Method::Delete | Method::Options => true,
+            Method::Post => self.retry_post,
+            _ => false,
+        }
+    }
+
+    //NOTE: attempt starts from 1, None means the result must be handed over as it is
+    pub fn retry_delay(&self, attempt: u32, result: &Result<Response, Error>) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return Option::None;
+        }
+
+        match result {
+            &Ok(ref response) => {
+                if !RetryPolicy::is_retryable_status(response.status()) {
+                    return Option::None;
+                }
+                match RetryPolicy::retry_after(response) {
+                    Some(retry_after) => {
+                        let max_delay = Duration::from_millis(self.max_delay_ms);
+                        if retry_after > max_delay {
+                            Option::Some(max_delay)
+                        }
+                        else {
+                            Option::Some(retry_after)
+                        }
+                    },
+                    None => Option::Some(self.backoff(attempt)),
+                }
+            },
+            &Err(ref err) => {
+                if !RetryPolicy::is_retryable_error(err) {
+                    return Option::None;
+                }
+                Option::Some(self.backoff(attempt))
+            },
+        }
+    }
+
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = if attempt > 32 { 31 } else { attempt - 1 };
+        let delay_ms = self.base_delay_ms.saturating_mul(1u64 << exponent);
+        let delay_ms = if delay_ms > self.max_delay_ms { self.max_delay_ms } else { delay_ms };
+
+        let jitter_ms = if self.jitter_ms > 0 {
+            rand::random::<u64>() % (self.jitter_ms + 1)
+        }
+        else {
+            0
+        };
+        Duration::from_millis(delay_ms + jitter_ms)
+    }
+
+    fn is_retryable_status(status: StatusCode) -> bool {
+        status == StatusCode::TooManyRequests || status.is_server_error()
+    }
+
+    fn is_retryable_error(err: &Error) -> bool {
+        match err {
+            &Error::Io(_) | &Error::Incomplete | &Error::Closed |
+            &Error::Cancel(_) | &Error::Timeout => true,
+            _ => false,
+        }
+    }
+
+    fn retry_after(response: &Response) -> Option<Duration> {
+        match response.headers().get::<RetryAfter>() {
+            Some(&RetryAfter::Delay(delay)) => Option::Some(delay),
+            Some(&RetryAfter::DateTime(date)) => {
+                let retry_time: SystemTime = date.into();
+                match retry_time.duration_since(SystemTime::now()) {
+                    Ok(delay) => Option::Some(delay),
+                    Err(_) => Option::Some(Duration::from_millis(0)),
+                }
+            },
+            None => Option::None,
+        }
+    }
+}
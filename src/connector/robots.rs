@@ -0,0 +1,280 @@
+use std::fmt;
+use std::error::Error;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use hyper::{Uri,StatusCode};
+
+pub const DEFAULT_ROBOTS_USER_AGENT: &'static str = "cruler";
+//NOTE: RFC 9309 lets crawlers stop parsing after 500 KiB
+pub const MAX_ROBOTS_SIZE: usize = 500 * 1024;
+
+//NOTE: carried inside an io error so that callers can tell a robots.txt skip from a failure
+#[derive(Debug)]
+pub struct RobotsDisallowed {
+    url: String,
+}
+
+impl RobotsDisallowed {
+    pub fn new(url: &str) -> RobotsDisallowed {
+        RobotsDisallowed {
+            url: url.to_owned(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+}
+
+impl fmt::Display for RobotsDisallowed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} is disallowed by robots.txt", self.url)
+    }
+}
+
+impl Error for RobotsDisallowed {
+    fn description(&self) -> &str {
+        "disallowed by robots.txt"
+    }
+}
+
+struct RobotsGroup {
+    user_agents: Vec<String>,
+    rules: Vec<(bool, String)>,
+    crawl_delay: Option<Duration>,
+}
+
+pub struct RobotsRules {
+    groups: Vec<RobotsGroup>,
+    disallow_all: bool,
+}
+
+impl RobotsRules {
+    pub fn parse(content: &str) -> RobotsRules {
+        let mut groups: Vec<RobotsGroup> = Vec::new();
+        let mut in_user_agents = false;
+
+        for line in content.lines() {
+            let line = match line.find('#') {
+                Some(index) => &line[..index],
+                None => line,
+            };
+            let (key, value) = match line.find(':') {
+                Some(index) => (line[..index].trim().to_lowercase(), line[index + 1..].trim()),
+                None => continue,
+            };
+
+            match key.as_str() {
+                "user-agent" => {
+                    if !in_user_agents {
+                        groups.push(RobotsGroup {
+                            user_agents: Vec::new(),
+                            rules: Vec::new(),
+                            crawl_delay: Option::None,
+                        });
+                        in_user_agents = true;
+                    }
+                    groups.last_mut().unwrap().user_agents.push(value.to_lowercase());
+                },
+                "allow" | "disallow" => {
+                    in_user_agents = false;
+                    //NOTE: an empty Disallow allows everything, so it adds no rule
+                    match groups.last_mut() {
+                        Some(group) if !value.is_empty() => {
+                            group.rules.push((key == "allow", value.to_owned()));
+                        },
+                        _ => { },
+                    }
+                },
+                "crawl-delay" => {
+                    in_user_agents = false;
+                    match (groups.last_mut(), value.parse::<f64>()) {
+                        (Some(group), Ok(seconds)) if seconds > 0.0 => {
+                            group.crawl_delay = Option::Some(Duration::from_millis((seconds * 1000.0) as u64));
+                        },
+                        _ => { },
+                    }
+                },
+                _ => { },
+            }
+        }
+
+        RobotsRules {
+            groups: groups,
+            disallow_all: false,
+        }
+    }
+
+    pub fn allow_all() -> RobotsRules {
+        RobotsRules {
+            groups: Vec::new(),
+            disallow_all: false,
+        }
+    }
+
+    pub fn disallow_all() -> RobotsRules {
+        RobotsRules {
+            groups: Vec::new(),
+            disallow_all: true,
+        }
+    }
+
+    //NOTE: 4xx means there is no robots.txt and everything is allowed,
+    //      anything else (5xx included) means it is unavailable and everything is disallowed (RFC 9309)
+    pub fn from_response(status: StatusCode, content: &[u8]) -> RobotsRules {
+        if status.is_success() {
+            let size = if content.len() > MAX_ROBOTS_SIZE { MAX_ROBOTS_SIZE } else { content.len() };
+            RobotsRules::parse(String::from_utf8_lossy(&content[..size]).as_ref())
+        }
+        else if status.is_client_error() {
+            RobotsRules::allow_all()
+        }
+        else {
+            RobotsRules::disallow_all()
+        }
+    }
+
+    //NOTE: the longest matching pattern wins and Allow wins a tie, /robots.txt itself is always allowed
+    pub fn is_allowed(&self, path: &str, user_agent: &str) -> bool {
+        if path == "/robots.txt" {
+            return true;
+        }
+        if self.disallow_all {
+            return false;
+        }
+
+        let mut matched: Option<(usize, bool)> = Option::None;
+        for group in self.groups_for(user_agent) {
+            for &(allow, ref pattern) in &group.rules {
+                if !RobotsRules::pattern_match(pattern.as_str(), path) {
+                    continue;
+                }
+                matched = match matched {
+                    Some((length, matched_allow)) if length > pattern.len() ||
+                        (length == pattern.len() && matched_allow) => Option::Some((length, matched_allow)),
+                    _ => Option::Some((pattern.len(), allow)),
+                };
+            }
+        }
+
+        match matched {
+            Some((_length, allow)) => allow,
+            None => true,
+        }
+    }
+
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<Duration> {
+        self.groups_for(user_agent).iter()
+            .filter_map(|group| group.crawl_delay)
+            .max()
+    }
+
+    //NOTE: groups naming the product token of the user agent, e.g. "cruler" of "cruler/0.1", and otherwise "*"
+    fn groups_for(&self, user_agent: &str) -> Vec<&RobotsGroup> {
+        let user_agent = user_agent.to_lowercase();
+        let product_token = user_agent.split(|c: char| c == '/' || c.is_whitespace())
+            .next()
+            .unwrap_or("");
+
+        let named: Vec<&RobotsGroup> = self.groups.iter()
+            .filter(|group| group.user_agents.iter().any(|agent| agent.as_str() == product_token))
+            .collect();
+        if !named.is_empty() {
+            return named;
+        }
+
+        self.groups.iter()
+            .filter(|group| group.user_agents.iter().any(|agent| agent.as_str() == "*"))
+            .collect()
+    }
+
+    //NOTE: "*" matches any sequence and a trailing "$" anchors the end of the path
+    fn pattern_match(pattern: &str, path: &str) -> bool {
+        let (pattern, anchored) = if pattern.ends_with('$') {
+            (&pattern[..pattern.len() - 1], true)
+        }
+        else {
+            (pattern, false)
+        };
+
+        let pieces: Vec<&str> = pattern.split('*').collect();
+        if !path.starts_with(pieces[0]) {
+            return false;
+        }
+
+        let mut position = pieces[0].len();
+        for (index, piece) in pieces.iter().enumerate().skip(1) {
+            let is_last = index == pieces.len() - 1;
+            if is_last && anchored {
+                return path.len() >= position + piece.len() && path.ends_with(piece);
+            }
+            match path[position..].find(piece) {
+                Some(found) => position += found + piece.len(),
+                None => return false,
+            }
+        }
+
+        !anchored || position == path.len()
+    }
+}
+
+//NOTE: keyed by scheme://host[:port], shared by the connectors of a run so each robots.txt is fetched once
+pub struct RobotsCache {
+    rules: RefCell<HashMap<String, RobotsRules>>,
+}
+
+impl RobotsCache {
+    pub fn new() -> RobotsCache {
+        RobotsCache {
+            rules: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn origin_of(uri: &Uri) -> Option<String> {
+        let scheme = uri.scheme().unwrap_or("http");
+        match (uri.host(), uri.port()) {
+            (Some(host), Some(port)) => Option::Some(format!("{}://{}:{}", scheme, host, port)),
+            (Some(host), None) => Option::Some(format!("{}://{}", scheme, host)),
+            (None, _) => Option::None,
+        }
+    }
+
+    pub fn contains(&self, origin: &str) -> bool {
+        self.rules.borrow().contains_key(origin)
+    }
+
+    pub fn insert(&self, origin: String, rules: RobotsRules) {
+        self.rules.borrow_mut().insert(origin, rules);
+    }
+
+    //NOTE: hosts whose robots.txt has not been loaded are allowed
+    pub fn is_allowed(&self, uri: &Uri, user_agent: &str) -> bool {
+        let origin = match RobotsCache::origin_of(uri) {
+            Some(origin) => origin,
+            None => return true,
+        };
+        let path = match uri.query() {
+            Some(query) => format!("{}?{}", uri.path(), query),
+            None => uri.path().to_owned(),
+        };
+
+        match self.rules.borrow().get(origin.as_str()) {
+            Some(rules) => rules.is_allowed(path.as_str(), user_agent),
+            None => true,
+        }
+    }
+
+    pub fn crawl_delay(&self, uri: &Uri, user_agent: &str) -> Option<Duration> {
+        let origin = match RobotsCache::origin_of(uri) {
+            Some(origin) => origin,
+            None => return Option::None,
+        };
+
+        match self.rules.borrow().get(origin.as_str()) {
+            Some(rules) => rules.crawl_delay(user_agent),
+            None => Option::None,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use configure::{ConnectorConfigure,ProxyConfigure,TlsConfigure};
+use connector::tokio_core::reactor;
+use connector::cookie_jar::CookieJar;
+use connector::auth::SecretsCache;
+use connector::robots::RobotsCache;
+use connector::transport::Transport;
+
+//NOTE: clients are told apart by the settings they are built with, the connect timeout included,
+//      the pool size is left out since it only bounds the requests a connector keeps in flight
+type ClientKey = (Option<ProxyConfigure>, Option<TlsConfigure>, Option<Duration>);
+
+//NOTE: state of one run that every connector made by Navigator and Extractor has to share
+#[derive(Clone)]
+pub struct Session {
+    core: Rc<RefCell<reactor::Core>>,
+    clients: Rc<RefCell<Vec<(ClientKey, Rc<Transport>)>>>,
+    cookie_jar: Rc<CookieJar>,
+    secrets_cache: Rc<SecretsCache>,
+    robots_cache: Rc<RobotsCache>,
+    transport: Option<Rc<Transport>>,
+    logins: Rc<RefCell<HashSet<String>>>,
+}
+
+impl Session {
+    pub fn new() -> Session {
+        Session {
+            core: Rc::new(RefCell::new(reactor::Core::new().unwrap())),
+            clients: Rc::new(RefCell::new(Vec::new())),
+            cookie_jar: Rc::new(CookieJar::new()),
+            secrets_cache: Rc::new(SecretsCache::new()),
+            robots_cache: Rc::new(RobotsCache::new()),
+            transport: Option::None,
+            logins: Rc::new(RefCell::new(HashSet::new())),
+        }
+    }
+
+    //NOTE: connectors of the session send through this transport instead of their own hyper client
+    pub fn with_transport(transport: Rc<Transport>) -> Session {
+        let mut session = Session::new();
+        session.transport = Option::Some(transport);
+        session
+    }
+
+    //NOTE: connectors of the session run one after another on this reactor,
+    //      the idle connections of their clients are kept on it between runs
+    pub fn core(&self) -> Rc<RefCell<reactor::Core>> {
+        self.core.clone()
+    }
+
+    //NOTE: the transport of the session when it has one, otherwise the client made for the proxy and tls
+    //      settings of configure and the connect timeout, make_client is only called the first time
+    //      those settings are seen and the client keeps them for the rest of the run
+    //      a client that can not be made is not kept, the error goes back to the connector
+    pub fn transport_for<F>(&self, configure: &ConnectorConfigure, connect_timeout: Option<Duration>,
+                            make_client: F) -> Result<Rc<Transport>, String>
+        where F: FnOnce() -> Result<Rc<Transport>, String> {
+        match self.transport {
+            Some(ref transport) => return Ok(transport.clone()),
+            None => { },
+        }
+
+        let key = (configure.get_proxy_configure().cloned(), configure.get_tls_configure().cloned(),
+                   connect_timeout);
+        let mut clients = self.clients.borrow_mut();
+        match clients.iter().find(|&&(ref client_key, _)| *client_key == key) {
+            Some(&(_, ref client)) => return Ok(client.clone()),
+            None => { },
+        }
+
+        let client = make_client()?;
+        clients.push((key, client.clone()));
+        Ok(client)
+    }
+
+    pub fn cookie_jar(&self) -> Rc<CookieJar> {
+        self.cookie_jar.clone()
+    }
+
+    pub fn secrets_cache(&self) -> Rc<SecretsCache> {
+        self.secrets_cache.clone()
+    }
+
+    pub fn robots_cache(&self) -> Rc<RobotsCache> {
+        self.robots_cache.clone()
+    }
+
+    pub fn transport(&self) -> Option<Rc<Transport>> {
+        self.transport.clone()
+    }
+
+    //NOTE: a login is done once per run, its cookies stay in the cookie jar
+    pub fn is_logged_in(&self, login_key: &str) -> bool {
+        self.logins.borrow().contains(login_key)
+    }
+
+    pub fn set_logged_in(&self, login_key: &str) {
+        self.logins.borrow_mut().insert(login_key.to_owned());
+    }
+}
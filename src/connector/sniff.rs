@@ -0,0 +1,119 @@
+use std::str::FromStr;
+
+//NOTE: enough for every signature below including the compatible brands of an ftyp box
+pub const SNIFF_SIZE: usize = 64;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SniffedType {
+    Jpeg,
+    Png,
+    Gif,
+    WebP,
+    Avif,
+    Mp4,
+    Zip,
+    Pdf,
+}
+
+impl SniffedType {
+    pub fn sniff(head: &[u8]) -> Option<SniffedType> {
+        if head.starts_with(&[0xff, 0xd8, 0xff]) {
+            Option::Some(SniffedType::Jpeg)
+        }
+        else if head.starts_with(&[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]) {
+            Option::Some(SniffedType::Png)
+        }
+        else if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+            Option::Some(SniffedType::Gif)
+        }
+        else if head.len() >= 12 && &head[..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+            Option::Some(SniffedType::WebP)
+        }
+        else if head.len() >= 12 && &head[4..8] == b"ftyp" {
+            SniffedType::sniff_ftyp(head)
+        }
+        else if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") ||
+            head.starts_with(b"PK\x07\x08") {
+            Option::Some(SniffedType::Zip)
+        }
+        else if head.starts_with(b"%PDF-") {
+            Option::Some(SniffedType::Pdf)
+        }
+        else {
+            Option::None
+        }
+    }
+
+    //NOTE: AVIF and MP4 share the ISO base media ftyp box, the brands tell them apart and HEIF is left unknown
+    fn sniff_ftyp(head: &[u8]) -> Option<SniffedType> {
+        let box_size = (head[0] as usize) << 24 | (head[1] as usize) << 16 |
+            (head[2] as usize) << 8 | head[3] as usize;
+        let box_end = if box_size > head.len() { head.len() } else { box_size };
+
+        let major_brand = &head[8..12];
+        let mut brands = vec![major_brand];
+        let mut offset = 16;
+        while offset + 4 <= box_end {
+            brands.push(&head[offset..offset + 4]);
+            offset += 4;
+        }
+
+        if brands.iter().any(|brand| *brand == b"avif" || *brand == b"avis") {
+            return Option::Some(SniffedType::Avif);
+        }
+        match major_brand {
+            b"heic" | b"heix" | b"hevc" | b"hevx" | b"mif1" | b"msf1" => Option::None,
+            _ => Option::Some(SniffedType::Mp4),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            &SniffedType::Jpeg => "jpeg",
+            &SniffedType::Png => "png",
+            &SniffedType::Gif => "gif",
+            &SniffedType::WebP => "webp",
+            &SniffedType::Avif => "avif",
+            &SniffedType::Mp4 => "mp4",
+            &SniffedType::Zip => "zip",
+            &SniffedType::Pdf => "pdf",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            &SniffedType::Jpeg => "jpg",
+            _ => self.name(),
+        }
+    }
+
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            &SniffedType::Jpeg => "image/jpeg",
+            &SniffedType::Png => "image/png",
+            &SniffedType::Gif => "image/gif",
+            &SniffedType::WebP => "image/webp",
+            &SniffedType::Avif => "image/avif",
+            &SniffedType::Mp4 => "video/mp4",
+            &SniffedType::Zip => "application/zip",
+            &SniffedType::Pdf => "application/pdf",
+        }
+    }
+}
+
+//NOTE: accepts the name, the extension or the media type, e.g. "jpeg", "jpg" or "image/jpeg"
+impl FromStr for SniffedType {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let lowercase = name.trim().to_lowercase();
+        let sniffed_types = [SniffedType::Jpeg, SniffedType::Png, SniffedType::Gif, SniffedType::WebP,
+                             SniffedType::Avif, SniffedType::Mp4, SniffedType::Zip, SniffedType::Pdf];
+        for sniffed_type in sniffed_types.iter() {
+            if lowercase == sniffed_type.name() || lowercase == sniffed_type.extension() ||
+                lowercase == sniffed_type.media_type() {
+                return Result::Ok(*sniffed_type);
+            }
+        }
+        Result::Err(format!("unknown content type : {}", name))
+    }
+}
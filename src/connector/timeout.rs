@@ -0,0 +1,113 @@
+use std::io;
+use std::rc::Rc;
+use std::cell::Cell;
+use std::time::Duration;
+
+use hyper::{Uri,Error};
+use hyper::client::{Connect,Service};
+
+use super::futures::{Future,Stream,Poll,Async};
+use super::futures::future;
+use super::tokio_core::reactor;
+
+//NOTE: wraps a connector so that establishing a connection fails with TimedOut after connect_timeout
+pub struct TimeoutConnector<C> {
+    connector: C,
+    handle: reactor::Handle,
+    connect_timeout: Rc<Cell<Option<Duration>>>,
+}
+
+impl<C: Connect> TimeoutConnector<C> {
+    pub fn new(connector: C, handle: &reactor::Handle,
+               connect_timeout: Rc<Cell<Option<Duration>>>) -> TimeoutConnector<C> {
+        TimeoutConnector {
+            connector: connector,
+            handle: handle.clone(),
+            connect_timeout: connect_timeout,
+        }
+    }
+}
+
+impl<C: Connect> Service for TimeoutConnector<C> {
+    type Request = Uri;
+    type Response = C::Output;
+    type Error = io::Error;
+    type Future = Box<Future<Item=C::Output, Error=io::Error>>;
+
+    fn call(&self, uri: Uri) -> Self::Future {
+        let connecting = self.connector.connect(uri);
+        let connect_timeout = match self.connect_timeout.get() {
+            Some(connect_timeout) => connect_timeout,
+            None => return Box::new(connecting),
+        };
+
+        let timer = match reactor::Timeout::new(connect_timeout, &self.handle) {
+            Ok(timer) => timer,
+            Err(err) => return Box::new(future::err(err)),
+        };
+        let timer = timer.then(|_| {
+            Err(io::Error::new(io::ErrorKind::TimedOut, "connect timed out"))
+        });
+
+        Box::new(connecting.select(timer)
+            .map(|(connected, _timer)| connected)
+            .map_err(|(err, _timer)| err))
+    }
+}
+
+//NOTE: fails with Error::Timeout when the inner stream yields nothing for the idle duration
+pub struct IdleTimeout<S> {
+    stream: S,
+    idle: Duration,
+    handle: reactor::Handle,
+    timer: Option<reactor::Timeout>,
+    timed_out: bool,
+}
+
+impl<S> IdleTimeout<S> {
+    pub fn new(stream: S, idle: Duration, handle: &reactor::Handle) -> IdleTimeout<S> {
+        IdleTimeout {
+            stream: stream,
+            idle: idle,
+            handle: handle.clone(),
+            timer: Option::None,
+            timed_out: false,
+        }
+    }
+}
+
+impl<S: Stream<Error=Error>> Stream for IdleTimeout<S> {
+    type Item = S::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<S::Item>, Error> {
+        if self.timed_out {
+            return Ok(Async::Ready(Option::None));
+        }
+
+        match self.stream.poll() {
+            Ok(Async::Ready(item)) => {
+                self.timer = Option::None;
+                return Ok(Async::Ready(item));
+            },
+            Ok(Async::NotReady) => { },
+            Err(err) => return Err(err),
+        }
+
+        if self.timer.is_none() {
+            match reactor::Timeout::new(self.idle, &self.handle) {
+                Ok(timer) => self.timer = Option::Some(timer),
+                Err(err) => return Err(Error::Io(err)),
+            }
+        }
+
+        match self.timer.as_mut().unwrap().poll() {
+            Ok(Async::Ready(())) => {
+                self.timed_out = true;
+                Err(Error::Timeout)
+            },
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(err) => Err(Error::Io(err)),
+        }
+    }
+}
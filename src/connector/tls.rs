@@ -0,0 +1,194 @@
+use std::io;
+use std::fs::File;
+use std::io::Read;
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+use hyper::Uri;
+use hyper::client::{Connect,Service};
+
+use connector::hyper_tls::{HttpsConnector,HttpsConnecting,MaybeHttpsStream};
+use connector::native_tls::{TlsConnector,TlsConnectorBuilder,Certificate};
+use configure::TlsConfigure;
+
+const PEM_CERTIFICATE_BEGIN: &'static str = "-----BEGIN CERTIFICATE-----";
+
+#[derive(Clone, Default)]
+pub struct TlsSettings {
+    ca_certs: Vec<String>,
+    client_cert: Option<String>,
+    client_key: Option<String>,
+    accept_invalid_certs: Vec<String>,
+}
+
+impl TlsSettings {
+    pub fn from_configure(configure: Option<&TlsConfigure>) -> TlsSettings {
+        let configure = match configure {
+            Some(configure) => configure,
+            None => return TlsSettings::default(),
+        };
+
+        let accept_invalid_certs: Vec<String> = configure.get_accept_invalid_certs().cloned().unwrap_or(Vec::new())
+            .into_iter()
+            .map(|host_pattern| host_pattern.trim().to_lowercase())
+            .filter(|host_pattern| !host_pattern.is_empty())
+            .collect();
+        for host_pattern in &accept_invalid_certs {
+            warn!("TlsSettings::from_configure - certificates of {} are accepted without verification", host_pattern);
+        }
+
+        TlsSettings {
+            ca_certs: configure.get_ca_certs().cloned().unwrap_or(Vec::new()),
+            client_cert: configure.get_client_cert().cloned(),
+            client_key: configure.get_client_key().cloned(),
+            accept_invalid_certs: accept_invalid_certs,
+        }
+    }
+
+    //NOTE: "*.example.com" matches the subdomains of example.com but not example.com itself
+    pub fn accepts_invalid_cert(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        self.accept_invalid_certs.iter().any(|host_pattern| {
+            *host_pattern == host || (host_pattern.starts_with("*.") && host.ends_with(&host_pattern[1..]))
+        })
+    }
+
+    //NOTE: fails when a setting can not be loaded, so that no request goes out without it
+    pub fn make_connector<T: Connect + Clone>(&self, http: T) -> io::Result<HostTlsConnector<T>> {
+        let verified = HttpsConnector::from((http.clone(), self.build_tls_connector(false)?));
+        let insecure = if self.accept_invalid_certs.is_empty() {
+            Option::None
+        }
+        else {
+            let mut insecure = HttpsConnector::from((http, self.build_tls_connector(true)?));
+            insecure.danger_disable_hostname_verification(true);
+            Option::Some(insecure)
+        };
+
+        Ok(HostTlsConnector {
+            settings: self.clone(),
+            verified: verified,
+            insecure: insecure,
+            warned_hosts: RefCell::new(HashSet::new()),
+        })
+    }
+
+    fn build_tls_connector(&self, insecure: bool) -> io::Result<TlsConnector> {
+        let mut builder = TlsConnector::builder().map_err(TlsSettings::io_error)?;
+        for ca_cert in &self.ca_certs {
+            match TlsSettings::add_root_certificates(&mut builder, ca_cert.as_str()) {
+                Ok(count) => debug!("TlsSettings::build_tls_connector - {} certificates from {}", count, ca_cert),
+                Err(err) => return Err(TlsSettings::settings_error(format!("{} is not trusted : {}", ca_cert, err))),
+            }
+        }
+        match (self.client_cert.as_ref(), self.client_key.as_ref()) {
+            (Some(client_cert), Some(client_key)) => {
+                match TlsSettings::set_client_identity(&mut builder, client_cert.as_str(), client_key.as_str()) {
+                    Ok(_) => { },
+                    Err(err) => {
+                        let msg = format!("client certificate {} can not be used : {}", client_cert, err);
+                        return Err(TlsSettings::settings_error(msg));
+                    },
+                }
+            },
+            (None, None) => { },
+            _ => return Err(TlsSettings::settings_error("client_cert and client_key have to be set together".to_owned())),
+        }
+        if insecure {
+            TlsSettings::disable_verification(&mut builder)?;
+        }
+        builder.build().map_err(TlsSettings::io_error)
+    }
+
+    //NOTE: a bundle may hold many certificates
+    fn add_root_certificates(builder: &mut TlsConnectorBuilder, path: &str) -> io::Result<usize> {
+        let mut content = String::new();
+        File::open(path)?.read_to_string(&mut content)?;
+
+        let mut count = 0;
+        for block in content.split(PEM_CERTIFICATE_BEGIN).skip(1) {
+            let pem = format!("{}{}", PEM_CERTIFICATE_BEGIN, block);
+            let certificate = Certificate::from_pem(pem.as_bytes()).map_err(TlsSettings::io_error)?;
+            builder.add_root_certificate(certificate).map_err(TlsSettings::io_error)?;
+            count += 1;
+        }
+        if count == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "no PEM certificate is found"));
+        }
+        Ok(count)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "ios")))]
+    fn set_client_identity(builder: &mut TlsConnectorBuilder, client_cert: &str, client_key: &str) -> io::Result<()> {
+        use connector::native_tls::backend::openssl::TlsConnectorBuilderExt;
+        use connector::openssl::x509::X509_FILETYPE_PEM;
+
+        let context = builder.builder_mut();
+        context.set_certificate_chain_file(client_cert).map_err(TlsSettings::io_error)?;
+        context.set_private_key_file(client_key, X509_FILETYPE_PEM).map_err(TlsSettings::io_error)?;
+        context.check_private_key().map_err(TlsSettings::io_error)
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "ios"))]
+    fn set_client_identity(_builder: &mut TlsConnectorBuilder, _client_cert: &str, _client_key: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "PEM client certificates need the OpenSSL backend"))
+    }
+
+    //NOTE: with the hostname check off as well, any certificate is accepted
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "ios")))]
+    fn disable_verification(builder: &mut TlsConnectorBuilder) -> io::Result<()> {
+        use connector::native_tls::backend::openssl::TlsConnectorBuilderExt;
+        use connector::openssl::ssl::SSL_VERIFY_NONE;
+
+        builder.builder_mut().set_verify(SSL_VERIFY_NONE);
+        Ok(())
+    }
+
+    //NOTE: only the hostname check could be turned off here, accept_invalid_certs would not do what it says
+    #[cfg(any(target_os = "macos", target_os = "windows", target_os = "ios"))]
+    fn disable_verification(_builder: &mut TlsConnectorBuilder) -> io::Result<()> {
+        Err(TlsSettings::settings_error("accept_invalid_certs needs the OpenSSL backend".to_owned()))
+    }
+
+    fn io_error<E: ::std::error::Error + Send + Sync + 'static>(err: E) -> io::Error {
+        io::Error::new(io::ErrorKind::Other, err)
+    }
+
+    fn settings_error(msg: String) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("tls settings : {}", msg))
+    }
+}
+
+//NOTE: https to a host of accept_invalid_certs goes through the connector that does not verify,
+//      everything else through the verifying one
+pub struct HostTlsConnector<T> {
+    settings: TlsSettings,
+    verified: HttpsConnector<T>,
+    insecure: Option<HttpsConnector<T>>,
+    warned_hosts: RefCell<HashSet<String>>,
+}
+
+impl<T: Connect> Service for HostTlsConnector<T> {
+    type Request = Uri;
+    type Response = MaybeHttpsStream<T::Output>;
+    type Error = io::Error;
+    type Future = HttpsConnecting<T::Output>;
+
+    fn call(&self, uri: Uri) -> Self::Future {
+        let is_https = uri.scheme() == Some("https");
+        let insecure = match (self.insecure.as_ref(), uri.host()) {
+            (Some(insecure), Some(host)) if is_https && self.settings.accepts_invalid_cert(host) => {
+                if self.warned_hosts.borrow_mut().insert(host.to_owned()) {
+                    warn!("HostTlsConnector::call - the certificate of {} is not verified", host);
+                }
+                Option::Some(insecure)
+            },
+            _ => Option::None,
+        };
+
+        match insecure {
+            Some(insecure) => insecure.call(uri),
+            None => self.verified.call(uri),
+        }
+    }
+}
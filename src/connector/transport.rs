@@ -0,0 +1,157 @@
+use std::io;
+use std::ptr;
+use std::slice;
+use std::rc::Rc;
+use std::cell::Cell;
+use std::ffi::{CStr,CString};
+use std::os::raw::{c_char,c_int,c_void};
+use std::time::Duration;
+
+use hyper::{self,Client,Request,Body,StatusCode};
+use hyper::client::{HttpConnector,Response};
+use hyper::header::Headers;
+
+use connector::futures::{future,Future,Stream};
+use connector::tokio_core::reactor;
+use connector::timeout::TimeoutConnector;
+use connector::proxy::{ProxyConnector,ProxySettings};
+use connector::tls::{TlsSettings,HostTlsConnector};
+
+//NOTE: sends a single request, redirects, retries, cookies, robots.txt and the cache are handled by Connector
+pub trait Transport {
+    fn request(&self, handle: &reactor::Handle, request: Request)
+        -> Box<Future<Item=Response, Error=hyper::Error>>;
+}
+
+type HttpsClient = Client<TimeoutConnector<HostTlsConnector<ProxyConnector>>,Body>;
+
+//NOTE: the default transport, bound to the reactor of the handle it is made with
+pub struct HyperTransport {
+    client: HttpsClient,
+}
+
+impl HyperTransport {
+    //NOTE: fails when the tls settings can not be loaded
+    pub fn new(pool_size: usize, handle: &reactor::Handle, proxy_settings: Rc<ProxySettings>,
+               tls_settings: &TlsSettings, connect_timeout: Rc<Cell<Option<Duration>>>) -> io::Result<HyperTransport> {
+        let mut http_connector = HttpConnector::new(pool_size, handle);
+        http_connector.enforce_http(false);
+        let proxy_connector = ProxyConnector::new(http_connector, proxy_settings);
+        let https_connector = tls_settings.make_connector(proxy_connector)?;
+        let client = Client::configure().
+            connector(TimeoutConnector::new(https_connector, handle, connect_timeout)).
+            build(handle);
+
+        Ok(HyperTransport {
+            client: client,
+        })
+    }
+}
+
+impl Transport for HyperTransport {
+    fn request(&self, _handle: &reactor::Handle, request: Request)
+        -> Box<Future<Item=Response, Error=hyper::Error>> {
+        Box::new(self.client.request(request))
+    }
+}
+
+//NOTE: filled in by a C transport callback, headers are "Name: value" lines separated by "\r\n",
+//      the pointers only have to stay valid until the callback is called again
+#[repr(C)]
+pub struct TransportResponse {
+    pub status: c_int,
+    pub headers: *const c_char,
+    pub body: *const u8,
+    pub body_len: usize,
+}
+
+//NOTE: method, url, "\r\n" separated request headers and the body in, 0 is returned on success,
+//      body is null when the request has none
+pub type TransportCallback = extern fn(user_data: *mut c_void, method: *const c_char, url: *const c_char,
+                                       headers: *const c_char, body: *const u8, body_len: usize,
+                                       response: *mut TransportResponse) -> c_int;
+
+//NOTE: lets the FFI side send requests with its own HTTP stack, the callback blocks the reactor while it runs
+pub struct CallbackTransport {
+    callback: TransportCallback,
+    user_data: *mut c_void,
+}
+
+impl CallbackTransport {
+    pub fn new(callback: TransportCallback, user_data: *mut c_void) -> CallbackTransport {
+        CallbackTransport {
+            callback: callback,
+            user_data: user_data,
+        }
+    }
+
+    fn call(&self, mut request: Request) -> Result<Response, hyper::Error> {
+        //NOTE: Connector only hands over bodies that are already in memory
+        let body = match request.body_mut().take() {
+            Some(body) => Option::Some(body.concat2().wait()?.to_vec()),
+            None => Option::None,
+        };
+        let mut raw_headers = String::new();
+        for header in request.headers().iter() {
+            raw_headers.push_str(format!("{}: {}\r\n", header.name(), header.value_string()).as_str());
+        }
+        let method = CallbackTransport::c_string(request.method().as_ref())?;
+        let url = CallbackTransport::c_string(request.uri().as_ref())?;
+        let raw_headers = CallbackTransport::c_string(raw_headers.as_str())?;
+
+        let mut response = TransportResponse {
+            status: 0,
+            headers: ptr::null(),
+            body: ptr::null(),
+            body_len: 0,
+        };
+        let (body_ptr, body_len) = match body {
+            Some(ref body) => (body.as_ptr(), body.len()),
+            None => (ptr::null(), 0),
+        };
+        let result = (self.callback)(self.user_data, method.as_ptr(), url.as_ptr(),
+                                     raw_headers.as_ptr(), body_ptr, body_len, &mut response);
+        if result != 0 {
+            let msg = format!("transport callback failed with {} for {}", result, request.uri());
+            return Err(hyper::Error::Io(io::Error::new(io::ErrorKind::Other, msg)));
+        }
+
+        let status = match StatusCode::try_from(response.status as u16) {
+            Ok(status) if response.status > 0 => status,
+            _ => {
+                let msg = format!("transport callback returned status {} for {}", response.status, request.uri());
+                return Err(hyper::Error::Io(io::Error::new(io::ErrorKind::InvalidData, msg)));
+            },
+        };
+        let mut headers = Headers::new();
+        if !response.headers.is_null() {
+            let raw_headers = unsafe { CStr::from_ptr(response.headers) };
+            for line in raw_headers.to_string_lossy().split("\r\n") {
+                match line.find(':') {
+                    Some(index) => headers.append_raw(line[..index].trim().to_owned(),
+                                                      line[index + 1..].trim().to_owned()),
+                    None => { },
+                }
+            }
+        }
+        let body = if response.body.is_null() {
+            Vec::new()
+        }
+        else {
+            unsafe { slice::from_raw_parts(response.body, response.body_len) }.to_vec()
+        };
+
+        Ok(Response::new().with_status(status).with_headers(headers).with_body(Body::from(body)))
+    }
+
+    fn c_string(value: &str) -> Result<CString, hyper::Error> {
+        CString::new(value).map_err(|err| hyper::Error::Io(io::Error::new(io::ErrorKind::InvalidInput, err)))
+    }
+}
+
+impl Transport for CallbackTransport {
+    fn request(&self, _handle: &reactor::Handle, request: Request)
+        -> Box<Future<Item=Response, Error=hyper::Error>> {
+        Box::new(future::result(self.call(request)))
+    }
+}
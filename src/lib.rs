@@ -15,8 +15,12 @@ pub mod result;
 
 use std::str::FromStr;
 
+use std::rc::Rc;
 use std::ffi::CStr;
-use std::os::raw::c_char;
+use std::os::raw::{c_char,c_void};
+
+use connector::session::Session;
+use connector::transport::{CallbackTransport,TransportCallback};
 
 #[no_mangle]
 pub extern fn cruler_extract_all_with_default_config() {
@@ -27,8 +31,10 @@ pub extern fn cruler_extract_all_with_default_config() {
     let ext_rules = ExtractionRules::default();
     let configure = Configure::default();
     let ext_configure = configure.get_extractor_configure().unwrap();
+    let connector_configure = configure.get_connector_configure();
 
-    let extractor = Extractor::new(&ext_rules, ext_configure);
+    let mut extractor = Extractor::new(&ext_rules, ext_configure, connector_configure);
+    extractor.set_login(configure.get_login_configure());
     extractor.extract_all();
 }
 
@@ -79,8 +85,10 @@ pub extern fn cruler_extract_all_from_raw(ext_rule_raw: *const c_char,
         configure
     };
     let ext_configure = configure.get_extractor_configure().unwrap();
+    let connector_configure = configure.get_connector_configure();
 
-    let extractor = Extractor::new(&ext_rules, ext_configure);
+    let mut extractor = Extractor::new(&ext_rules, ext_configure, connector_configure);
+    extractor.set_login(configure.get_login_configure());
     extractor.extract_all();
 }
 
@@ -104,7 +112,34 @@ pub extern fn cruler_extract_all(config_path: *const c_char) {
     let extract_file_path = get_file_path(root_path, "extraction_rules.toml");
 
     let ext_rules = ExtractionRules::new(extract_file_path.as_str());
-    extractor_extract_all(&ext_rules, config_file_path.as_str());
+    extractor_extract_all(&ext_rules, config_file_path.as_str(), Session::new());
+}
+
+//NOTE: same as cruler_extract_all, but every request is sent by the transport callback
+#[no_mangle]
+pub extern fn cruler_extract_all_with_transport(config_path: *const c_char,
+                                                transport: TransportCallback,
+                                                user_data: *mut c_void) {
+    use connector::extractor::extraction_rules::ExtractionRules;
+
+    let config_root_path = unsafe {
+        let root_path = CStr::from_ptr(config_path);
+        root_path.to_owned()
+    };
+    let root_path = match config_root_path.to_str() {
+        Result::Ok(root_path) => root_path,
+        Result::Err(_err) => {
+            error!("cruler_extract_all_with_transport - root_path error");
+            return;
+        }
+    };
+
+    let config_file_path = get_file_path(root_path, "configure.toml");
+    let extract_file_path = get_file_path(root_path, "extraction_rules.toml");
+
+    let ext_rules = ExtractionRules::new(extract_file_path.as_str());
+    let session = Session::with_transport(Rc::new(CallbackTransport::new(transport, user_data)));
+    extractor_extract_all(&ext_rules, config_file_path.as_str(), session);
 }
 
 #[inline(always)]
@@ -117,13 +152,15 @@ fn get_file_path(root_path: &str, file_name: &str) -> String {
 
 #[inline(always)]
 fn extractor_extract_all(ext_rules: &connector::extractor::extraction_rules::ExtractionRules,
-                         config_path: &str) {
+                         config_path: &str, session: Session) {
     use configure::Configure;
     use connector::extractor::Extractor;
 
     let configure = Configure::new(config_path);
     let ext_configure = configure.get_extractor_configure().unwrap();
+    let connector_configure = configure.get_connector_configure();
 
-    let extractor = Extractor::new(&ext_rules, ext_configure);
+    let mut extractor = Extractor::with_session(&ext_rules, ext_configure, connector_configure, session);
+    extractor.set_login(configure.get_login_configure());
     extractor.extract_all();
 }
\ No newline at end of file
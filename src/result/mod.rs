@@ -6,7 +6,6 @@ use std::string::{ToString};
 use std::collections::HashMap;
 
 use hyper::{Uri};
-use hyper::header::Raw;
 
 pub enum ExtraInformKey {
     SourceUrl,
@@ -46,16 +45,14 @@ impl ToString for ExtraInformKey {
 
 pub struct ResultHandler {
     root_path: Option<String>,
-    raw_cookies: Option<Raw>,
     result_map: HashMap<String, Vec<String>>,
     extra_informs: HashMap<String, String>,
 }
 
 impl ResultHandler {
-    pub fn new(root_path: Option<String>, raw_cookies: Option<Raw>) -> ResultHandler {
+    pub fn new(root_path: Option<String>) -> ResultHandler {
         ResultHandler {
             root_path: root_path,
-            raw_cookies: raw_cookies,
             result_map: HashMap::new(),
             extra_informs: HashMap::new(),
         }
@@ -73,10 +70,6 @@ impl ResultHandler {
         self.extra_informs.get(key.to_string().as_str())
     }
 
-    pub fn get_raw_cookies(&self) -> Option<Raw> {
-        return self.raw_cookies.clone()
-    }
-
     pub fn get_label_names(&self) -> Vec<String> {
         let result_map = &self.result_map;
         let mut label_names = Vec::new();